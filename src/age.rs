@@ -0,0 +1,55 @@
+//! Whole-container encryption with [age](https://age-encryption.org), so existing age keys
+//! can be used instead of a crate-specific scheme. The output is a standard age file and can
+//! be decrypted with the `age` CLI as well.
+
+use std::error::Error;
+use std::io::{Read, Write};
+use std::iter;
+use ::age::{Decryptor, Encryptor, Identity, Recipient};
+use crate::Container;
+
+/// Serializes `container` and encrypts it to every recipient.
+pub fn encrypt(container: &Container, recipients: Vec<Box<dyn Recipient + Send>>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let encryptor = Encryptor::with_recipients(recipients).ok_or("at least one recipient is needed")?;
+
+    let mut encrypted: Vec<u8> = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut encrypted)?;
+    writer.write_all(&container.to_bytes()?)?;
+    writer.finish()?;
+    Ok(encrypted)
+}
+
+/// Decrypts an age file made for `identity` and parses the container inside.
+pub fn decrypt(encrypted: &[u8], identity: &dyn Identity) -> Result<Container, Box<dyn Error>> {
+    let decryptor = match Decryptor::new(encrypted)? {
+        Decryptor::Recipients(decryptor) => decryptor,
+        Decryptor::Passphrase(_) => return Err("container is encrypted with a passphrase, not to a recipient".into())
+    };
+
+    let mut bytes: Vec<u8> = Vec::new();
+    decryptor.decrypt(iter::once(identity))?.read_to_end(&mut bytes)?;
+    Container::from_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::age::x25519;
+    use crate::File;
+
+    #[test]
+    fn encrypt_round_trips() {
+        let mut container = Container::with_timestamp("Secret", 0);
+        container.add_file(File::new("key.txt", vec![0x01, 0x02]));
+
+        let identity = x25519::Identity::generate();
+        let encrypted = encrypt(&container, vec![Box::new(identity.to_public())]).unwrap();
+        assert!(Container::from_bytes(&encrypted).is_err());
+
+        let decrypted = decrypt(&encrypted, &identity).unwrap();
+        assert_eq!(decrypted.get_file("key.txt".to_string()).unwrap().content, vec![0x01, 0x02]);
+
+        assert!(decrypt(&encrypted, &x25519::Identity::generate()).is_err());
+        assert!(encrypt(&container, Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use crate::prelude::*;
+use crate::escape::{escape, unescape};
+
+/// Name of the entry `Container` keeps its audit log in once `audit_actor` is set.
+pub const AUDIT_ENTRY: &str = ".fofc-audit";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    /// An entry was added, or replaced one with the same name.
+    Add,
+    Remove,
+    Rename { to: String }
+}
+
+/// One line of a container's audit log, from `Container::audit_log`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// Seconds since the Unix epoch. Zero without `std`, which has no clock.
+    pub time: u64,
+    pub actor: String,
+    pub action: AuditAction,
+    /// The entry acted on, by its name before the change.
+    pub name: String
+}
+
+impl AuditRecord {
+    /// A tab-separated line: time, actor, action and name, then the new name for renames.
+    pub(crate) fn to_line(&self) -> String {
+        let (action, to) = match &self.action {
+            AuditAction::Add => ("add", None),
+            AuditAction::Remove => ("remove", None),
+            AuditAction::Rename { to } => ("rename", Some(to))
+        };
+
+        let mut line = format!("{}\t{}\t{action}\t{}", self.time, escape(&self.actor), escape(&self.name));
+        if let Some(to) = to {
+            line.push('\t');
+            line.push_str(&escape(to));
+        }
+        line.push('\n');
+        line
+    }
+
+    pub(crate) fn from_line(line: &str) -> Option<AuditRecord> {
+        let mut fields = line.split('\t');
+        let time = fields.next()?.parse().ok()?;
+        let actor = unescape(fields.next()?);
+        let action = fields.next()?;
+        let name = unescape(fields.next()?);
+        let action = match (action, fields.next()) {
+            ("add", None) => AuditAction::Add,
+            ("remove", None) => AuditAction::Remove,
+            ("rename", Some(to)) => AuditAction::Rename { to: unescape(to) },
+            _ => return None
+        };
+
+        fields.next().is_none().then_some(AuditRecord { time, actor, action, name })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_round_trip() {
+        let record = AuditRecord {
+            time: 5,
+            actor: "ci\tbot".to_string(),
+            action: AuditAction::Rename { to: "new\nname".to_string() },
+            name: "a\\b".to_string()
+        };
+        let line = record.to_line();
+        assert_eq!(line, "5\tci\\tbot\trename\ta\\\\b\tnew\\nname\n");
+        assert_eq!(AuditRecord::from_line(line.trim_end_matches('\n')), Some(record));
+
+        assert_eq!(AuditRecord::from_line("5\tme\tadd\ta\textra"), None);
+        assert_eq!(AuditRecord::from_line("x\tme\tadd\ta"), None);
+    }
+}
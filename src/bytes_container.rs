@@ -0,0 +1,81 @@
+use bytes::Bytes;
+use core::error::Error;
+use crate::prelude::*;
+use crate::{Container, ContainerView, File, ParseOptions};
+
+/// A parsed container whose entry contents are reference-counted slices of one shared buffer.
+///
+/// Like `ContainerView`, but owning its input, so it can be cloned and handed to other threads
+/// without a lifetime. Cloning it or any of its entries copies the names and never the content,
+/// which suits servers that share one bundle across request handlers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BytesContainer {
+    pub comment: String,
+    pub x: u64,
+    pub y: u64,
+    pub z: u64,
+    pub files: Vec<BytesFile>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BytesFile {
+    pub name: String,
+    pub content: Bytes
+}
+
+impl BytesContainer {
+    pub fn parse(bytes: Bytes) -> Result<BytesContainer, Box<dyn Error>> {
+        BytesContainer::parse_with_options(bytes, &ParseOptions::default())
+    }
+
+    pub fn parse_with_options(bytes: Bytes, options: &ParseOptions) -> Result<BytesContainer, Box<dyn Error>> {
+        let view = ContainerView::parse_with_options(&bytes, options)?;
+        let files = view.files.into_iter().map(|f| BytesFile {
+            name: f.name.into_owned(),
+            content: match f.content {
+                Cow::Borrowed(content) => bytes.slice_ref(content),
+                Cow::Owned(content) => Bytes::from(content)
+            }
+        }).collect();
+
+        Ok(BytesContainer { comment: view.comment.into_owned(), x: view.x, y: view.y, z: view.z, files })
+    }
+
+    pub fn get_file(&self, name: &str) -> Option<&BytesFile> {
+        self.files.iter().find(|f| f.name == name)
+    }
+
+    /// Copies the contents out of the shared buffer into an editable `Container`.
+    pub fn to_container(&self) -> Container {
+        let files: Vec<File> = self.files.iter().map(BytesFile::to_file).collect();
+        Container::from_parts(self.comment.clone(), self.x, files)
+    }
+}
+
+impl BytesFile {
+    pub fn to_file(&self) -> File {
+        File::new(self.name.as_str(), self.content.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_slices_input() {
+        let mut container = Container::with_timestamp("Shared", 0);
+        container.add_file(File::new("a.bin", vec![0x01, 0x02, 0x03]));
+        container.add_file(File::new("empty", vec![]));
+        let bytes = Bytes::from(container.to_bytes().unwrap());
+
+        let shared = BytesContainer::parse(bytes.clone()).unwrap();
+        let content = &shared.get_file("a.bin").unwrap().content;
+        assert_eq!(content[..], [0x01, 0x02, 0x03]);
+        assert!(bytes.as_ptr_range().contains(&content.as_ptr()));
+
+        let copy = shared.clone();
+        assert_eq!(copy.files[0].content.as_ptr(), content.as_ptr());
+        assert_eq!(shared.to_container(), container);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::prelude::*;
+use crate::Container;
+
+/// Entry-level differences between two containers, from `Container::diff`.
+///
+/// Entries are matched by name, using the first entry of each name as `get_file` does.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContainerDiff {
+    /// Names only the other container has, in its order.
+    pub added: Vec<String>,
+    /// Names only this container has, in its order.
+    pub removed: Vec<String>,
+    /// Names whose content differs, in this container's order.
+    pub modified: Vec<EntryChange>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryChange {
+    pub name: String,
+    pub old_size: u64,
+    pub new_size: u64,
+    /// SHA-256 of the old content. Only computed with the `sha2` feature, `None` otherwise.
+    pub old_digest: Option<[u8; 32]>,
+    pub new_digest: Option<[u8; 32]>
+}
+
+impl EntryChange {
+    pub fn size_delta(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+}
+
+impl ContainerDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+pub(crate) fn diff(old: &Container, new: &Container) -> ContainerDiff {
+    let mut diff = ContainerDiff::default();
+    for (i, f) in old.files.iter().enumerate() {
+        if old.position(&f.name) != Some(i) {
+            continue;
+        }
+        match new.position(&f.name) {
+            Some(j) if new.files[j].content != f.content => diff.modified.push(EntryChange {
+                name: f.name.clone(),
+                old_size: f.content.len() as u64,
+                new_size: new.files[j].content.len() as u64,
+                old_digest: digest(&f.content),
+                new_digest: digest(&new.files[j].content)
+            }),
+            Some(_) => {}
+            None => diff.removed.push(f.name.clone())
+        }
+    }
+
+    for (i, f) in new.files.iter().enumerate() {
+        if new.position(&f.name) == Some(i) && old.position(&f.name).is_none() {
+            diff.added.push(f.name.clone());
+        }
+    }
+
+    diff
+}
+
+#[cfg(feature = "sha2")]
+fn digest(content: &[u8]) -> Option<[u8; 32]> {
+    use sha2::Digest;
+    Some(sha2::Sha256::digest(content).into())
+}
+
+#[cfg(not(feature = "sha2"))]
+fn digest(_: &[u8]) -> Option<[u8; 32]> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::File;
+
+    #[test]
+    fn diff_reports_entry_changes() {
+        let mut old = Container::with_timestamp("v1", 1);
+        old.add_file(File::new("same", vec![0x01]));
+        old.add_file(File::new("changed", vec![0x02; 10]));
+        old.add_file(File::new("gone", vec![0x03]));
+
+        let mut new = Container::with_timestamp("v2", 2);
+        new.add_file(File::new("new", vec![0x04]));
+        new.add_file(File::new("changed", vec![0x02; 4]));
+        new.add_file(File::new("same", vec![0x01]));
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, ["new"]);
+        assert_eq!(diff.removed, ["gone"]);
+        let change = &diff.modified[0];
+        assert_eq!((diff.modified.len(), change.name.as_str(), change.old_size, change.new_size), (1, "changed", 10, 4));
+        assert_eq!(change.size_delta(), -6);
+        #[cfg(feature = "sha2")]
+        assert_eq!(change.new_digest.map(|d| d[..4].to_vec()), Some(vec![0xbb, 0x72, 0xb4, 0xe4]));
+        #[cfg(not(feature = "sha2"))]
+        assert_eq!((change.old_digest, change.new_digest), (None, None));
+        assert!(old.diff(&old).is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::prelude::*;
+use crate::{Container, File, Map};
+
+/// Entries with byte-for-byte identical content, from `Container::find_duplicates`.
+#[derive(Clone, Debug)]
+pub struct DuplicateGroup<'a> {
+    /// In the order they appear in `files`. Always at least two.
+    pub files: Vec<&'a File>,
+    /// Bytes that storing the content once would save.
+    pub wasted_bytes: u64
+}
+
+pub(crate) fn find_duplicates(container: &Container) -> Vec<DuplicateGroup<'_>> {
+    let mut groups: Vec<Vec<&File>> = Vec::new();
+    let mut by_content: Map<&[u8], usize> = Map::new();
+
+    for f in container.files.iter().filter(|f| !f.content.is_empty()) {
+        match by_content.get(&f.content[..]) {
+            Some(&i) => groups[i].push(f),
+            None => {
+                by_content.insert(&f.content[..], groups.len());
+                groups.push(vec![f]);
+            }
+        }
+    }
+
+    groups.into_iter().filter(|files| files.len() > 1).map(|files| DuplicateGroup {
+        wasted_bytes: (files.len() as u64 - 1) * files[0].content.len() as u64,
+        files
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_identical_contents() {
+        let mut container = Container::with_timestamp("Dups", 0);
+        container.add_file(File::new("en/a.json", vec![0x01; 10]));
+        container.add_file(File::new("de/a.json", vec![0x01; 10]));
+        container.add_file(File::new("unique", vec![0x02]));
+        container.add_file(File::new("fr/a.json", vec![0x01; 10]));
+        container.add_file(File::new("empty1", vec![]));
+        container.add_file(File::new("empty2", vec![]));
+
+        let groups = find_duplicates(&container);
+        assert_eq!(groups.len(), 1);
+        let names: Vec<&str> = groups[0].files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["en/a.json", "de/a.json", "fr/a.json"]);
+        assert_eq!(groups[0].wasted_bytes, 20);
+    }
+}
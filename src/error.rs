@@ -0,0 +1,174 @@
+use core::error::Error;
+use core::fmt;
+use crate::prelude::*;
+
+/// Which of the `ParseOptions` limits, or the cap given to `Container::set_comment_with_limit`,
+/// was hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    FileSize,
+    TotalSize,
+    FileCount,
+    NameLength,
+    CommentLength
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::FileSize => write!(f, "file size"),
+            Limit::TotalSize => write!(f, "total size"),
+            Limit::FileCount => write!(f, "file count"),
+            Limit::NameLength => write!(f, "name length"),
+            Limit::CommentLength => write!(f, "comment length")
+        }
+    }
+}
+
+/// Why `validate_name` rejected a name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameProblem {
+    Empty,
+    /// A NUL byte would end the name early when the container is read back.
+    ContainsNul,
+    ControlCharacter
+}
+
+impl fmt::Display for NameProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameProblem::Empty => write!(f, "is empty"),
+            NameProblem::ContainsNul => write!(f, "contains a NUL byte"),
+            NameProblem::ControlCharacter => write!(f, "contains a control character")
+        }
+    }
+}
+
+/// The part of the encoding a parse error was found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Magic,
+    Comment,
+    Timestamp,
+    FileCount,
+    FileName,
+    FileLength,
+    FileContent,
+    TrailingData
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Magic => write!(f, "magic number"),
+            Field::Comment => write!(f, "comment"),
+            Field::Timestamp => write!(f, "timestamp"),
+            Field::FileCount => write!(f, "file count field"),
+            Field::FileName => write!(f, "file name"),
+            Field::FileLength => write!(f, "file length field"),
+            Field::FileContent => write!(f, "file content"),
+            Field::TrailingData => write!(f, "trailing data")
+        }
+    }
+}
+
+/// Errors raised by this crate. Functions still return `Box<dyn Error>`, so match on
+/// these with `downcast_ref::<FofcError>()`.
+#[derive(Debug)]
+pub enum FofcError {
+    /// Wraps any failure found while parsing with where it happened.
+    Parse { entry: Option<usize>, field: Field, offset: u64, reason: Box<FofcError> },
+    /// The input ended in the middle of a field.
+    UnexpectedEof,
+    /// An entry declared more content than the input has left.
+    TruncatedEntry { declared: u64, remaining: u64 },
+    /// The first byte wasn't `MAGIC_NUMBER`.
+    BadMagic { found: u8 },
+    /// The input declared more than a `ParseOptions` limit allows.
+    LimitExceeded { limit: Limit, value: u64, max: u64 },
+    /// Strict parsing found bytes after the last entry.
+    TrailingData { length: u64 },
+    /// Strict parsing found an entry with an empty name.
+    EmptyName,
+    /// Strict parsing found an entry name that isn't valid UTF-8.
+    NameEncoding,
+    /// An entry with this name already exists and the duplicate policy is `Reject`.
+    DuplicateName { name: String },
+    /// A name was rejected by `validate_name`.
+    InvalidName { name: String, problem: NameProblem },
+    /// The operation's `CancellationToken` was triggered.
+    Cancelled,
+    /// `Container::write_to_slice` was given a buffer shorter than the serialized container.
+    BufferTooSmall { needed: u64, available: u64 },
+    /// An entry's content is too big to address in memory on this platform, which can happen
+    /// above 4 GiB on 32-bit targets. `LazyContainer::copy_entry_to` can still stream it.
+    EntryTooLargeForPlatform { length: u64 },
+    /// A patch copies an entry the container it's applied to doesn't have.
+    PatchBase { index: usize, entries: usize },
+    /// An encoded patch entry has an unknown tag or a short copy record.
+    InvalidPatch { entry: usize },
+    /// A signature or MAC didn't match the data it was checked against.
+    BadSignature,
+    /// A manifest line isn't in `sha256sum` format. Lines count from 1.
+    InvalidManifest { line: usize },
+    /// `add_file` was given an entry named `AUDIT_ENTRY` while auditing is on.
+    AuditEntry,
+    /// Another handle holds a conflicting lock on the container's file.
+    WouldBlock,
+    /// A comment contained a NUL byte, which would end it early when the container is read back.
+    CommentContainsNul
+}
+
+impl fmt::Display for FofcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FofcError::Parse { entry, field, offset, reason } => {
+                if let Some(entry) = entry {
+                    write!(f, "entry {entry}: ")?;
+                }
+                write!(f, "{field} at offset {offset:#x}: {reason}")
+            }
+            FofcError::UnexpectedEof => write!(f, "unexpected end of input"),
+            FofcError::TruncatedEntry { declared, remaining } => {
+                write!(f, "declared length of {declared} exceeds the {remaining} bytes of remaining input")
+            }
+            FofcError::BadMagic { found } => write!(f, "invalid or incorrect magic number {found:#04x}"),
+            FofcError::LimitExceeded { limit, value, max } => {
+                write!(f, "{limit} of {value} exceeds the limit of {max}")
+            }
+            FofcError::TrailingData { length } => write!(f, "{length} bytes of trailing data after the last entry"),
+            FofcError::EmptyName => write!(f, "entry has an empty name"),
+            FofcError::NameEncoding => write!(f, "entry name is not valid UTF-8"),
+            FofcError::DuplicateName { name } => write!(f, "an entry named {name:?} already exists"),
+            FofcError::InvalidName { name, problem } => write!(f, "entry name {name:?} {problem}"),
+            FofcError::Cancelled => write!(f, "operation was cancelled"),
+            FofcError::BufferTooSmall { needed, available } => {
+                write!(f, "buffer of {available} bytes is too small for {needed} bytes of output")
+            }
+            FofcError::EntryTooLargeForPlatform { length } => {
+                write!(f, "entry of {length} bytes is too large to hold in memory on this platform")
+            }
+            FofcError::PatchBase { index, entries } => {
+                write!(f, "patch copies entry {index} but the container has {entries} entries")
+            }
+            FofcError::InvalidPatch { entry } => write!(f, "patch entry {entry} is malformed"),
+            FofcError::BadSignature => write!(f, "signature does not match the data"),
+            FofcError::InvalidManifest { line } => write!(f, "manifest line {line} is malformed"),
+            FofcError::AuditEntry => write!(f, "the audit log entry can only be appended to by auditing"),
+            FofcError::WouldBlock => write!(f, "the file is locked by another handle"),
+            FofcError::CommentContainsNul => write!(f, "comment contains a NUL byte"),
+        }
+    }
+}
+
+impl FofcError {
+    /// The underlying error, without any location attached by the parser.
+    pub fn kind(&self) -> &FofcError {
+        match self {
+            FofcError::Parse { reason, .. } => reason.kind(),
+            other => other
+        }
+    }
+}
+
+impl Error for FofcError {}
@@ -0,0 +1,48 @@
+use crate::prelude::*;
+
+/// Backslash-escapes what would break line- and tab-separated text.
+pub(crate) fn escape(field: &str) -> String {
+    escape_chars(field, &['\\', '\t', '\n', '\r'])
+}
+
+/// Like `escape`, but leaving tabs alone, for line-separated text such as `sha256sum` output.
+#[cfg(feature = "signing")]
+pub(crate) fn escape_lines(text: &str) -> String {
+    escape_chars(text, &['\\', '\n', '\r'])
+}
+
+fn escape_chars(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if !special.contains(&c) => escaped.push(c),
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+/// Reverses `escape` and `escape_lines`. Backslashes not followed by an escape are kept as
+/// they are.
+pub(crate) fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('\\')) => unescaped.push('\\'),
+            ('\\', Some('t')) => unescaped.push('\t'),
+            ('\\', Some('n')) => unescaped.push('\n'),
+            ('\\', Some('r')) => unescaped.push('\r'),
+            _ => {
+                unescaped.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    unescaped
+}
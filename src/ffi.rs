@@ -0,0 +1,176 @@
+//! C API for reading containers.
+//!
+//! Build a C-compatible library with `cargo rustc --release --features ffi --crate-type cdylib`
+//! (or `staticlib`). Every function returns a `FofcStatus`; outputs go through pointer arguments.
+//! Pointers handed out borrow from the container and stay valid until `fofc_free` is called on it.
+
+use std::ptr;
+use std::slice;
+use crate::{Container, FofcError};
+
+/// An opened container. Only ever handled through a pointer on the C side.
+pub struct FofcContainer {
+    container: Container
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FofcStatus {
+    Ok = 0,
+    NullPointer = 1,
+    /// The input isn't a valid container.
+    ParseError = 2,
+    /// The input ended before the container did.
+    Truncated = 3,
+    NotFound = 4,
+    OutOfRange = 5,
+    /// A name passed in isn't valid UTF-8, so no entry can have it.
+    InvalidName = 6
+}
+
+/// Parses `len` bytes at `data` and stores the new container in `*out`.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes and `out` must be valid for writes.
+/// The container must be released with `fofc_free`.
+#[no_mangle]
+pub unsafe extern "C" fn fofc_open(data: *const u8, len: usize, out: *mut *mut FofcContainer) -> FofcStatus {
+    if data.is_null() || out.is_null() {
+        return FofcStatus::NullPointer;
+    }
+
+    let bytes = slice::from_raw_parts(data, len);
+    match Container::from_bytes(bytes) {
+        Ok(container) => {
+            *out = Box::into_raw(Box::new(FofcContainer { container }));
+            FofcStatus::Ok
+        }
+        Err(e) => match e.downcast_ref::<FofcError>().map(FofcError::kind) {
+            Some(FofcError::UnexpectedEof | FofcError::TruncatedEntry { .. }) => FofcStatus::Truncated,
+            _ => FofcStatus::ParseError
+        }
+    }
+}
+
+/// Stores the number of entries in `*count`.
+///
+/// # Safety
+/// `container` must come from `fofc_open` and `count` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fofc_file_count(container: *const FofcContainer, count: *mut usize) -> FofcStatus {
+    if container.is_null() || count.is_null() {
+        return FofcStatus::NullPointer;
+    }
+
+    let container = &(*container).container;
+    *count = container.files.len();
+    FofcStatus::Ok
+}
+
+/// Stores the name of the entry at `index` in `*name` and `*name_len`. Names aren't NUL-terminated.
+///
+/// # Safety
+/// `container` must come from `fofc_open` and `name` and `name_len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fofc_file_name(container: *const FofcContainer, index: usize, name: *mut *const u8, name_len: *mut usize) -> FofcStatus {
+    if container.is_null() || name.is_null() || name_len.is_null() {
+        return FofcStatus::NullPointer;
+    }
+
+    let container = &(*container).container;
+    match container.files.get(index) {
+        Some(f) => {
+            *name = f.name.as_ptr();
+            *name_len = f.name.len();
+            FofcStatus::Ok
+        }
+        None => FofcStatus::OutOfRange
+    }
+}
+
+/// Looks up the entry called `name` (`name_len` bytes of UTF-8) and stores its content in
+/// `*content` and `*content_len`. An empty entry's content is stored as a null pointer with a
+/// length of 0. Fails with `InvalidName` if `name` isn't valid UTF-8.
+///
+/// # Safety
+/// `container` must come from `fofc_open`, `name` must point to `name_len` readable bytes,
+/// and `content` and `content_len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fofc_get_file(
+    container: *const FofcContainer,
+    name: *const u8,
+    name_len: usize,
+    content: *mut *const u8,
+    content_len: *mut usize
+) -> FofcStatus {
+    if container.is_null() || name.is_null() || content.is_null() || content_len.is_null() {
+        return FofcStatus::NullPointer;
+    }
+
+    let container = &(*container).container;
+    let Ok(name) = std::str::from_utf8(slice::from_raw_parts(name, name_len)) else {
+        return FofcStatus::InvalidName;
+    };
+    match container.get_file(name.to_string()) {
+        Some(f) => {
+            *content = if f.content.is_empty() { ptr::null() } else { f.content.as_ptr() };
+            *content_len = f.content.len();
+            FofcStatus::Ok
+        }
+        None => FofcStatus::NotFound
+    }
+}
+
+/// Releases a container from `fofc_open`. Passing null does nothing.
+///
+/// # Safety
+/// `container` must come from `fofc_open` and not have been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn fofc_free(container: *mut FofcContainer) {
+    if !container.is_null() {
+        drop(Box::from_raw(container));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::File;
+
+    #[test]
+    fn open_and_read() {
+        let mut container = Container::new("FFI").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01, 0x02]));
+        container.add_file(File::new("\u{FFFD}", vec![]));
+        let bytes = container.to_bytes().unwrap();
+
+        unsafe {
+            let mut handle: *mut FofcContainer = ptr::null_mut();
+            assert_eq!(fofc_open(bytes.as_ptr(), bytes.len(), &mut handle), FofcStatus::Ok);
+
+            let mut count = 0;
+            assert_eq!(fofc_file_count(handle, &mut count), FofcStatus::Ok);
+            assert_eq!(count, 2);
+
+            let (mut name, mut name_len) = (ptr::null(), 0);
+            assert_eq!(fofc_file_name(handle, 0, &mut name, &mut name_len), FofcStatus::Ok);
+            assert_eq!(slice::from_raw_parts(name, name_len), b"a.bin");
+            assert_eq!(fofc_file_name(handle, 2, &mut name, &mut name_len), FofcStatus::OutOfRange);
+
+            let (mut content, mut content_len) = (ptr::null(), 0);
+            assert_eq!(fofc_get_file(handle, b"a.bin".as_ptr(), 5, &mut content, &mut content_len), FofcStatus::Ok);
+            assert_eq!(slice::from_raw_parts(content, content_len), &[0x01, 0x02]);
+            assert_eq!(fofc_get_file(handle, b"b".as_ptr(), 1, &mut content, &mut content_len), FofcStatus::NotFound);
+            assert_eq!(fofc_get_file(handle, [0xFF].as_ptr(), 1, &mut content, &mut content_len), FofcStatus::InvalidName);
+
+            let replacement = "\u{FFFD}".as_bytes();
+            assert_eq!(fofc_get_file(handle, replacement.as_ptr(), replacement.len(), &mut content, &mut content_len), FofcStatus::Ok);
+            assert!(content.is_null());
+            assert_eq!(content_len, 0);
+
+            fofc_free(handle);
+
+            assert_eq!(fofc_open(bytes.as_ptr(), bytes.len() - 1, &mut handle), FofcStatus::Truncated);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Read-only FUSE adapter, so existing tools can browse a container without extracting it.
+//!
+//! The layout follows the crate's shared directory tree: `/`-separated names become
+//! directories, and names that can't be mapped to a path are left out.
+
+use std::ffi::OsStr;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use fuser::{FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry, Request};
+use libc::{EISDIR, ENOENT, ENOTDIR};
+use crate::vfs::{Node, Tree};
+use crate::Container;
+
+/// Nothing changes while mounted, so the kernel can cache everything for a while.
+const TTL: Duration = Duration::from_secs(60);
+
+/// A container served as a read-only filesystem. Pass it to `fuser::mount2` or
+/// `fuser::spawn_mount2`, or use `mount`.
+pub struct ContainerFs {
+    container: Container,
+    tree: Tree,
+    mtime: SystemTime
+}
+
+impl ContainerFs {
+    pub fn new(container: Container) -> ContainerFs {
+        ContainerFs {
+            tree: Tree::new(&container),
+            mtime: UNIX_EPOCH + Duration::from_secs(container.x),
+            container
+        }
+    }
+
+    pub fn container(&self) -> &Container {
+        &self.container
+    }
+
+    fn attr(&self, ino: u64, req: &Request<'_>) -> Option<FileAttr> {
+        let (kind, size, perm, nlink) = match self.tree.node(ino)? {
+            Node::Directory { .. } => (FileType::Directory, 0, 0o555, 2),
+            Node::File { entry } => (FileType::RegularFile, self.container.files[*entry].content.len() as u64, 0o444, 1)
+        };
+
+        Some(FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: self.mtime,
+            mtime: self.mtime,
+            ctime: self.mtime,
+            crtime: self.mtime,
+            kind,
+            perm,
+            nlink,
+            uid: req.uid(),
+            gid: req.gid(),
+            rdev: 0,
+            blksize: 512,
+            flags: 0
+        })
+    }
+}
+
+impl Filesystem for ContainerFs {
+    fn lookup(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        match name.to_str().and_then(|name| self.tree.child(parent, name)).and_then(|ino| self.attr(ino, req)) {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(ENOENT)
+        }
+    }
+
+    fn getattr(&mut self, req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+        match self.attr(ino, req) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(ENOENT)
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData
+    ) {
+        match self.tree.node(ino) {
+            Some(Node::File { entry }) => {
+                let content = &self.container.files[*entry].content;
+                let start = usize::try_from(offset).unwrap_or(0).min(content.len());
+                let end = start.saturating_add(size as usize).min(content.len());
+                reply.data(&content[start..end]);
+            }
+            Some(Node::Directory { .. }) => reply.error(EISDIR),
+            None => reply.error(ENOENT)
+        }
+    }
+
+    fn readdir(&mut self, _req: &Request<'_>, ino: u64, _fh: u64, offset: i64, mut reply: ReplyDirectory) {
+        let (parent, children) = match self.tree.node(ino) {
+            Some(Node::Directory { parent, children }) => (*parent, children),
+            Some(Node::File { .. }) => return reply.error(ENOTDIR),
+            None => return reply.error(ENOENT)
+        };
+
+        let listing = [(ino, FileType::Directory, "."), (parent, FileType::Directory, "..")].into_iter()
+            .chain(children.iter().map(|(name, &child)| {
+                let kind = match self.tree.node(child) {
+                    Some(Node::Directory { .. }) => FileType::Directory,
+                    _ => FileType::RegularFile
+                };
+                (child, kind, name.as_str())
+            }));
+
+        // Each entry's offset is where the next readdir call should resume.
+        for (i, (child, kind, name)) in listing.enumerate().skip(usize::try_from(offset).unwrap_or(0)) {
+            if reply.add(child, i as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// Mounts `container` read-only at `mountpoint` and serves it until it's unmounted.
+pub fn mount(container: Container, mountpoint: impl AsRef<Path>) -> io::Result<()> {
+    let options = [MountOption::RO, MountOption::FSName("fofc".to_string())];
+    fuser::mount2(ContainerFs::new(container), mountpoint, &options)
+}
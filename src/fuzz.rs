@@ -0,0 +1,47 @@
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+use crate::prelude::*;
+use crate::{Container, File};
+
+/// NULs end strings in the encoding, so generated values leave them out to stay round-trippable.
+fn arbitrary_string(u: &mut Unstructured<'_>) -> Result<String> {
+    Ok(String::arbitrary(u)?.replace('\0', ""))
+}
+
+impl<'a> Arbitrary<'a> for File {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<File> {
+        Ok(File::new(arbitrary_string(u)?, Vec::<u8>::arbitrary(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Container {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Container> {
+        let comment = arbitrary_string(u)?;
+        let x = u64::arbitrary(u)?;
+        // the encoding stores the entry count as a u16
+        let files = u.arbitrary_iter::<File>()?.take(u16::MAX as usize).collect::<Result<Vec<File>>>()?;
+
+        Ok(Container::from_parts(comment, x, files))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arbitrary_containers_round_trip() {
+        for seed in 0..64u64 {
+            let data: Vec<u8> = (0..512u64).map(|i| (i.wrapping_mul(seed + 31) ^ (i >> 3)) as u8).collect();
+            let container = Container::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+            let parsed = Container::from_bytes(&container.to_bytes().unwrap()).unwrap();
+            assert_eq!(parsed.comment, container.comment);
+            assert_eq!(parsed.x, container.x);
+            assert_eq!(parsed.files.len(), container.files.len());
+            for (a, b) in parsed.files.iter().zip(container.files.iter()) {
+                assert_eq!(a.name, b.name);
+                assert_eq!(a.content, b.content);
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::prelude::*;
+
+/// A compiled glob pattern. `?` matches any one character and `*` any run of characters, both
+/// within a single path component; `**` also crosses `/`, and `**/` matches zero or more whole
+/// directories. `[...]` is a character class with `a-z` ranges, negated by a leading `!`.
+/// A `[` without a closing `]` is taken literally.
+#[derive(Clone, Debug)]
+pub(crate) struct Glob {
+    pattern: Vec<char>
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> Glob {
+        Glob { pattern: pattern.chars().collect() }
+    }
+
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        let name: Vec<char> = name.chars().collect();
+        let mut matcher = Matcher { p: &self.pattern, n: &name, memo: vec![None; (self.pattern.len() + 1) * (name.len() + 1)] };
+        matcher.at(0, 0)
+    }
+}
+
+/// Backtracking over stars, remembering which (pattern, name) positions have been tried so
+/// patterns with many stars stay polynomial instead of exponential.
+struct Matcher<'a> {
+    p: &'a [char],
+    n: &'a [char],
+    memo: Vec<Option<bool>>
+}
+
+impl Matcher<'_> {
+    fn at(&mut self, pi: usize, ni: usize) -> bool {
+        let slot = pi * (self.n.len() + 1) + ni;
+        if let Some(known) = self.memo[slot] {
+            return known;
+        }
+        let matched = self.step(pi, ni);
+        self.memo[slot] = Some(matched);
+        matched
+    }
+
+    fn step(&mut self, pi: usize, ni: usize) -> bool {
+        let (p, n) = (self.p, self.n);
+        match &p[pi..] {
+            [] => ni == n.len(),
+            ['*', '*', '/', ..] => {
+                self.at(pi + 3, ni) || (ni..n.len()).any(|i| n[i] == '/' && self.at(pi + 3, i + 1))
+            }
+            ['*', '*', ..] => (ni..=n.len()).any(|i| self.at(pi + 2, i)),
+            ['*', ..] => {
+                for i in ni..=n.len() {
+                    if self.at(pi + 1, i) {
+                        return true;
+                    }
+                    if n.get(i) == Some(&'/') {
+                        return false;
+                    }
+                }
+                false
+            }
+            ['?', ..] => matches!(n.get(ni), Some(&c) if c != '/') && self.at(pi + 1, ni + 1),
+            ['[', class @ ..] => match class_match(class, n.get(ni).copied()) {
+                Some((matched, used)) => matched && self.at(pi + 1 + used, ni + 1),
+                None => n.get(ni) == Some(&'[') && self.at(pi + 1, ni + 1)
+            },
+            [c, ..] => n.get(ni) == Some(c) && self.at(pi + 1, ni + 1)
+        }
+    }
+}
+
+/// Matches `c` against the class body after `[`. Returns whether it matched and how much of
+/// `class` the body and its `]` took, or None if the class is never closed.
+fn class_match(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let negated = class.first() == Some(&'!');
+    let mut i = usize::from(negated);
+    let mut matched = false;
+
+    // a `]` straight after the opening is part of the class
+    let mut first = true;
+    loop {
+        match class.get(i)? {
+            ']' if !first => break,
+            &start => {
+                let (end, used) = match (class.get(i + 1), class.get(i + 2)) {
+                    (Some('-'), Some(&end)) if end != ']' => (end, 3),
+                    _ => (start, 1)
+                };
+                matched |= c.is_some_and(|c| start <= c && c <= end);
+                i += used;
+            }
+        }
+        first = false;
+    }
+
+    Some((c.is_some_and(|c| c != '/') && matched != negated, i + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_semantics() {
+        let cases = [
+            ("*.png", "a.png", true),
+            ("*.png", "dir/a.png", false),
+            ("assets/**/*.png", "assets/a.png", true),
+            ("assets/**/*.png", "assets/ui/icons/a.png", true),
+            ("assets/**/*.png", "assets/ui/a.jpg", false),
+            ("**", "any/thing", true),
+            ("file?.txt", "file1.txt", true),
+            ("file?.txt", "file/.txt", false),
+            ("[a-c]*.txt", "b.txt", true),
+            ("[!a-c]*.txt", "b.txt", false),
+            ("[]x].txt", "].txt", true),
+            ("[oops", "[oops", true),
+            ("é?", "éü", true)
+        ];
+
+        for (pattern, name, expected) in cases {
+            assert_eq!(Glob::new(pattern).matches(name), expected, "{pattern} against {name}");
+        }
+    }
+
+    #[test]
+    fn many_stars_stay_fast() {
+        // exponential backtracking would take far longer than the test suite to finish this
+        let name = "a".repeat(200);
+        assert!(!Glob::new(&format!("{}b", "*a".repeat(40))).matches(&name));
+        assert!(!Glob::new(&format!("{}b", "**a".repeat(40))).matches(&name));
+        assert!(Glob::new(&"*a".repeat(40)).matches(&name));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::prelude::*;
+use crate::File;
+
+/// A mutation with what's needed to reverse it.
+#[derive(Clone, Debug)]
+pub(crate) enum Edit {
+    /// `file` went in at `index`, replacing `replaced` if there was one.
+    Added { index: usize, file: File, replaced: Option<File> },
+    /// Entries called `name` came out of these positions, in ascending order.
+    Removed { name: String, entries: Vec<(usize, File)> },
+    Renamed { from: String, to: String, indices: Vec<usize> }
+}
+
+impl Edit {
+    pub(crate) fn undo(&self, files: &mut Vec<File>) {
+        match self {
+            Edit::Added { index, replaced: Some(old), .. } => set(files, *index, old),
+            Edit::Added { index, replaced: None, .. } => {
+                if *index < files.len() {
+                    files.remove(*index);
+                }
+            }
+            Edit::Removed { entries, .. } => {
+                for (index, file) in entries {
+                    files.insert((*index).min(files.len()), file.clone());
+                }
+            }
+            Edit::Renamed { from, indices, .. } => rename(files, indices, from)
+        }
+    }
+
+    pub(crate) fn redo(&self, files: &mut Vec<File>) {
+        match self {
+            Edit::Added { index, file, replaced: Some(_) } => set(files, *index, file),
+            Edit::Added { index, file, replaced: None } => files.insert((*index).min(files.len()), file.clone()),
+            Edit::Removed { name, .. } => files.retain(|f| f.name != *name),
+            Edit::Renamed { to, indices, .. } => rename(files, indices, to)
+        }
+    }
+}
+
+fn set(files: &mut [File], index: usize, file: &File) {
+    if let Some(slot) = files.get_mut(index) {
+        *slot = file.clone();
+    }
+}
+
+fn rename(files: &mut [File], indices: &[usize], name: &str) {
+    for &i in indices {
+        if let Some(f) = files.get_mut(i) {
+            f.name = name.to_string();
+        }
+    }
+}
+
+/// The undo and redo stacks behind `Container::undo` and `Container::redo`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct History {
+    pub(crate) done: Vec<Edit>,
+    pub(crate) undone: Vec<Edit>
+}
+
+impl History {
+    /// A new edit makes anything undone unreachable.
+    pub(crate) fn record(&mut self, edit: Edit) {
+        self.done.push(edit);
+        self.undone.clear();
+    }
+}
@@ -0,0 +1,48 @@
+use alloc::sync::Arc;
+use core::fmt;
+use crate::prelude::*;
+use crate::File;
+
+type AddHook = Arc<dyn Fn(&File) + Send + Sync>;
+type RemoveHook = Arc<dyn Fn(&str) + Send + Sync>;
+type RenameHook = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Callbacks registered on a `Container`, run in registration order after each mutation.
+#[derive(Clone, Default)]
+pub(crate) struct Observers {
+    add: Vec<AddHook>,
+    remove: Vec<RemoveHook>,
+    rename: Vec<RenameHook>
+}
+
+impl Observers {
+    pub(crate) fn on_add(&mut self, hook: impl Fn(&File) + Send + Sync + 'static) {
+        self.add.push(Arc::new(hook));
+    }
+
+    pub(crate) fn on_remove(&mut self, hook: impl Fn(&str) + Send + Sync + 'static) {
+        self.remove.push(Arc::new(hook));
+    }
+
+    pub(crate) fn on_rename(&mut self, hook: impl Fn(&str, &str) + Send + Sync + 'static) {
+        self.rename.push(Arc::new(hook));
+    }
+
+    pub(crate) fn added(&self, file: &File) {
+        self.add.iter().for_each(|hook| hook(file));
+    }
+
+    pub(crate) fn removed(&self, name: &str) {
+        self.remove.iter().for_each(|hook| hook(name));
+    }
+
+    pub(crate) fn renamed(&self, from: &str, to: &str) {
+        self.rename.iter().for_each(|hook| hook(from, to));
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({} add, {} remove, {} rename)", self.add.len(), self.remove.len(), self.rename.len())
+    }
+}
@@ -0,0 +1,170 @@
+use core::fmt;
+use crate::prelude::*;
+use crate::view::Reader;
+use crate::{Field, FofcError, MAGIC_NUMBER};
+
+/// How many bytes of each region `LayoutReport` shows in hex.
+const PREVIEW_LENGTH: usize = 16;
+
+/// One field of the encoding, as found by `inspect_layout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// Entry the field belongs to, or `None` for the header and trailing data.
+    pub entry: Option<usize>,
+    pub field: Field,
+    pub offset: u64,
+    /// Includes the NUL terminator for the comment and names.
+    pub length: u64
+}
+
+/// Every region of a serialized container in input order, for comparing the bytes another
+/// implementation wrote against what this one expects. Printing it gives one line per
+/// region with its offset, length and leading bytes in hex.
+#[derive(Debug)]
+pub struct LayoutReport<'a> {
+    bytes: &'a [u8],
+    pub regions: Vec<Region>,
+    /// Why the walk stopped early. Regions up to that point are still listed.
+    pub error: Option<FofcError>
+}
+
+impl LayoutReport<'_> {
+    /// The bytes `region` covers, or `None` if it reaches past the input.
+    pub fn bytes(&self, region: &Region) -> Option<&[u8]> {
+        let start = usize::try_from(region.offset).ok()?;
+        let end = start.checked_add(usize::try_from(region.length).ok()?)?;
+        self.bytes.get(start..end)
+    }
+}
+
+impl fmt::Display for LayoutReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for region in self.regions.iter() {
+            let label = match region.entry {
+                Some(entry) => format!("entry {entry} {}", region.field),
+                None => region.field.to_string()
+            };
+            write!(f, "{:#010x}  {label:<28} {:>8}  ", region.offset, region.length)?;
+
+            let bytes = self.bytes(region).unwrap_or(&[]);
+            for (i, b) in bytes.iter().take(PREVIEW_LENGTH).enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{b:02x}")?;
+            }
+            if bytes.len() > PREVIEW_LENGTH {
+                write!(f, " ...")?;
+            }
+            writeln!(f)?;
+        }
+
+        if let Some(error) = &self.error {
+            writeln!(f, "error: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds a region covering whatever `read` consumed, even if it then fails on the value.
+fn track<'a, T>(
+    reader: &mut Reader<'a>,
+    regions: &mut Vec<Region>,
+    field: Field,
+    read: impl FnOnce(&mut Reader<'a>) -> Result<T, FofcError>
+) -> Result<T, FofcError> {
+    let offset = reader.position();
+    let value = read(reader)?;
+    regions.push(Region { entry: reader.entry, field, offset, length: reader.position() - offset });
+    Ok(value)
+}
+
+fn walk(reader: &mut Reader<'_>, regions: &mut Vec<Region>) -> Result<(), FofcError> {
+    let magic = track(reader, regions, Field::Magic, |r| r.u8(Field::Magic))?;
+    if magic != MAGIC_NUMBER {
+        return Err(reader.error(Field::Magic, 0, FofcError::BadMagic { found: magic }));
+    }
+
+    track(reader, regions, Field::Comment, |r| r.until_0x00(Field::Comment))?;
+    track(reader, regions, Field::Timestamp, |r| r.u64(Field::Timestamp))?;
+    let file_count = track(reader, regions, Field::FileCount, |r| r.u16(Field::FileCount))?;
+
+    for i in 0..file_count as usize {
+        reader.entry = Some(i);
+        track(reader, regions, Field::FileName, |r| r.until_0x00(Field::FileName))?;
+        let length = track(reader, regions, Field::FileLength, |r| r.u64(Field::FileLength))?;
+        track(reader, regions, Field::FileContent, |r| r.slice(Field::FileContent, length))?;
+    }
+
+    reader.entry = None;
+    let trailing = reader.remaining().len() as u64;
+    if trailing > 0 {
+        regions.push(Region { entry: None, field: Field::TrailingData, offset: reader.position(), length: trailing });
+    }
+    Ok(())
+}
+
+/// Walks the byte layout of a serialized container without decoding names or copying content.
+/// Malformed input isn't an error: the report lists what was found before the problem.
+pub fn inspect_layout(bytes: &[u8]) -> LayoutReport<'_> {
+    let mut reader = Reader { bytes, position: 0, entry: None };
+    let mut regions = Vec::new();
+    let error = walk(&mut reader, &mut regions).err();
+    LayoutReport { bytes, regions, error }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Container, File};
+
+    #[test]
+    fn lists_every_region() {
+        let mut container = Container::with_timestamp("C", 1);
+        container.add_file(File::new("a", vec![0xAB; 20]));
+        let mut bytes = container.to_bytes().unwrap();
+        bytes.push(0xFF);
+
+        let report = inspect_layout(&bytes);
+        assert!(report.error.is_none());
+        let fields: Vec<(Option<usize>, Field, u64, u64)> = report.regions.iter().map(|r| (r.entry, r.field, r.offset, r.length)).collect();
+        assert_eq!(fields, [
+            (None, Field::Magic, 0, 1),
+            (None, Field::Comment, 1, 2),
+            (None, Field::Timestamp, 3, 8),
+            (None, Field::FileCount, 11, 2),
+            (Some(0), Field::FileName, 13, 2),
+            (Some(0), Field::FileLength, 15, 8),
+            (Some(0), Field::FileContent, 23, 20),
+            (None, Field::TrailingData, 43, 1)
+        ]);
+
+        let text = report.to_string();
+        assert!(text.starts_with("0x00000000  magic number                        1  46\n"));
+        assert!(text.contains("entry 0 file content               20  ab ab ab ab ab ab ab ab ab ab ab ab ab ab ab ab ...\n"));
+    }
+
+    #[test]
+    fn region_bytes_stay_in_bounds() {
+        let bytes = Container::with_timestamp("C", 1).to_bytes().unwrap();
+        let report = inspect_layout(&bytes);
+        assert_eq!(report.bytes(&report.regions[0]), Some(&[0x46][..]));
+
+        let past_end = Region { entry: None, field: Field::TrailingData, offset: bytes.len() as u64, length: 1 };
+        assert_eq!(report.bytes(&past_end), None);
+        let overflowing = Region { entry: None, field: Field::TrailingData, offset: u64::MAX, length: 2 };
+        assert_eq!(report.bytes(&overflowing), None);
+    }
+
+    #[test]
+    fn stops_at_the_first_problem() {
+        let mut container = Container::with_timestamp("C", 1);
+        container.add_file(File::new("a", vec![0x01; 4]));
+        let bytes = container.to_bytes().unwrap();
+
+        let report = inspect_layout(&bytes[..bytes.len() - 1]);
+        assert_eq!(report.regions.last().map(|r| r.field), Some(Field::FileLength));
+        assert!(matches!(report.error, Some(FofcError::Parse { entry: Some(0), field: Field::FileContent, .. })));
+        assert!(report.to_string().ends_with("error: entry 0: file content at offset 0x17: unexpected end of input\n"));
+    }
+}
@@ -0,0 +1,480 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::io;
+use crate::{Container, DuplicatePolicy, Field, File, FofcError, Limit, ParseMode, ParseOptions, Progress};
+use crate::view::{check_limit, decode_name};
+use crate::{ContainerStorage, MAGIC_NUMBER, Y_DIFFERENCE, Z_DIFFERENCE};
+
+/// How much of an entry `copy_entry_to` holds in memory at once.
+const COPY_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Where an entry's content sits in the storage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryLocation {
+    pub name: String,
+    /// Offset of the first content byte.
+    pub offset: u64,
+    pub length: u64
+}
+
+/// A container whose headers have been read but whose contents stay in storage until asked for.
+///
+/// Opening only walks the entry headers, skipping over content, so large containers can be
+/// listed and picked from without reading them in full.
+#[derive(Debug)]
+pub struct LazyContainer<S> {
+    pub comment: String,
+    pub x: u64,
+    pub y: u64,
+    pub z: u64,
+    entries: Vec<EntryLocation>,
+    positions: HashMap<String, usize>,
+    cache: EntryCache,
+    storage: S
+}
+
+/// Least-recently-used cache of entry contents, bounded by their total size.
+#[derive(Debug, Default)]
+struct EntryCache {
+    capacity: u64,
+    size: u64,
+    clock: u64,
+    /// Entry index to its content and when it was last used.
+    entries: HashMap<usize, (Vec<u8>, u64)>,
+    /// When each cached entry was last used to its index, oldest first.
+    order: BTreeMap<u64, usize>
+}
+
+impl EntryCache {
+    fn get(&mut self, index: usize) -> Option<Vec<u8>> {
+        let (content, used) = self.entries.get_mut(&index)?;
+        self.clock += 1;
+        self.order.remove(used);
+        self.order.insert(self.clock, index);
+        *used = self.clock;
+        Some(content.clone())
+    }
+
+    fn insert(&mut self, index: usize, content: &[u8]) {
+        let length = content.len() as u64;
+        if self.capacity == 0 || length > self.capacity {
+            return;
+        }
+
+        self.remove(index);
+        self.evict_to(self.capacity - length);
+        self.clock += 1;
+        self.size += length;
+        self.order.insert(self.clock, index);
+        self.entries.insert(index, (content.to_vec(), self.clock));
+    }
+
+    fn remove(&mut self, index: usize) {
+        if let Some((content, used)) = self.entries.remove(&index) {
+            self.order.remove(&used);
+            self.size -= content.len() as u64;
+        }
+    }
+
+    /// Drops the least recently used entries until at most `target` bytes are cached.
+    fn evict_to(&mut self, target: u64) {
+        while self.size > target {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some((content, _)) = self.entries.remove(&oldest) {
+                self.size -= content.len() as u64;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.size = 0;
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// Reads fields out of storage, tagging failures like the in-memory parser does.
+struct StorageReader<'s, S> {
+    storage: &'s mut S,
+    position: u64,
+    len: u64,
+    entry: Option<usize>
+}
+
+impl<S: ContainerStorage> StorageReader<'_, S> {
+    fn error(&self, field: Field, offset: u64, reason: FofcError) -> FofcError {
+        FofcError::Parse { entry: self.entry, field, offset, reason: Box::new(reason) }
+    }
+
+    fn remaining(&self) -> u64 {
+        self.len - self.position
+    }
+
+    fn fill(&mut self, field: Field, buf: &mut [u8]) -> Result<(), Box<dyn Error>> {
+        if (buf.len() as u64) > self.remaining() {
+            return Err(Box::new(self.error(field, self.position, FofcError::UnexpectedEof)));
+        }
+
+        self.storage.read_at(self.position, buf)?;
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    fn u8(&mut self, field: Field) -> Result<u8, Box<dyn Error>> {
+        let mut buf = [0; 1];
+        self.fill(field, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn u16(&mut self, field: Field) -> Result<u16, Box<dyn Error>> {
+        let mut buf = [0; 2];
+        self.fill(field, &mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    fn u64(&mut self, field: Field) -> Result<u64, Box<dyn Error>> {
+        let mut buf = [0; 8];
+        self.fill(field, &mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Reads up to the next NUL in chunks, giving up once more than `max` bytes have been seen.
+    fn until_0x00(&mut self, field: Field, max: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+        let offset = self.position;
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut chunk = [0; 256];
+
+        loop {
+            let size = (chunk.len() as u64).min(self.remaining()) as usize;
+            if size == 0 {
+                return Err(Box::new(self.error(field, offset, FofcError::UnexpectedEof)));
+            }
+
+            self.storage.read_at(self.position, &mut chunk[..size])?;
+            let (end, found) = match chunk[..size].iter().position(|&b| b == 0x00) {
+                Some(end) => (end, true),
+                None => (size, false)
+            };
+            bytes.extend_from_slice(&chunk[..end]);
+            self.position += end as u64 + found as u64;
+
+            if bytes.len() > max {
+                let e = FofcError::LimitExceeded { limit: Limit::NameLength, value: bytes.len() as u64, max: max as u64 };
+                return Err(Box::new(self.error(field, offset, e)));
+            }
+            if found {
+                return Ok(bytes);
+            }
+        }
+    }
+}
+
+impl<S: ContainerStorage> LazyContainer<S> {
+    pub fn open(storage: S) -> Result<LazyContainer<S>, Box<dyn Error>> {
+        LazyContainer::open_with_options(storage, &ParseOptions::default())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn open_with_options(mut storage: S, options: &ParseOptions) -> Result<LazyContainer<S>, Box<dyn Error>> {
+        let len = storage.len()?;
+        let mut reader = StorageReader { storage: &mut storage, position: 0, len, entry: None };
+
+        let magic = reader.u8(Field::Magic)?;
+        if magic != MAGIC_NUMBER {
+            return Err(Box::new(reader.error(Field::Magic, 0, FofcError::BadMagic { found: magic })));
+        }
+
+        let comment = String::from_utf8_lossy(&reader.until_0x00(Field::Comment, usize::MAX)?).into_owned();
+        let x = reader.u64(Field::Timestamp)?;
+        let count_offset = reader.position;
+        let file_count = reader.u16(Field::FileCount)?;
+        check_limit(Limit::FileCount, file_count as u64, options.max_file_count as u64)
+            .map_err(|e| reader.error(Field::FileCount, count_offset, e))?;
+
+        let mut entries: Vec<EntryLocation> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut total_size: u64 = 0;
+
+        for entry in 0..file_count as usize {
+            options.check_cancelled()?;
+            reader.entry = Some(entry);
+            let name_offset = reader.position;
+            let raw_name = reader.until_0x00(Field::FileName, options.max_name_length)?;
+            let name = decode_name(&raw_name, options).map(Cow::into_owned).map_err(|e| reader.error(Field::FileName, name_offset, e))?;
+
+            let length_offset = reader.position;
+            let length = reader.u64(Field::FileLength)?;
+            let remaining = reader.remaining();
+            if length > remaining {
+                let e = FofcError::TruncatedEntry { declared: length, remaining };
+                return Err(Box::new(reader.error(Field::FileLength, length_offset, e)));
+            }
+            total_size = total_size.saturating_add(length);
+            check_limit(Limit::FileSize, length, options.max_file_size)
+                .and_then(|_| check_limit(Limit::TotalSize, total_size, options.max_total_size))
+                .map_err(|e| reader.error(Field::FileLength, length_offset, e))?;
+
+            let location = EntryLocation { name, offset: reader.position, length };
+            reader.position += length;
+            #[cfg(feature = "tracing")]
+            tracing::trace!(entry, name = %location.name, offset = location.offset, length, "indexed entry");
+            options.report_progress(&Progress {
+                entry,
+                entry_count: file_count as usize,
+                entry_name: &location.name,
+                bytes_processed: reader.position,
+                total_bytes: len
+            });
+
+            match positions.get(&location.name) {
+                None => {
+                    positions.insert(location.name.clone(), entries.len());
+                    entries.push(location);
+                }
+                Some(&i) => match options.duplicate_policy {
+                    DuplicatePolicy::AllowDuplicates => entries.push(location),
+                    DuplicatePolicy::ReplaceExisting => entries[i] = location,
+                    DuplicatePolicy::Reject => {
+                        let e = FofcError::DuplicateName { name: location.name };
+                        return Err(Box::new(reader.error(Field::FileName, name_offset, e)));
+                    }
+                }
+            }
+        }
+        reader.entry = None;
+
+        let trailing = reader.remaining();
+        if options.mode == ParseMode::Strict && trailing > 0 {
+            let e = FofcError::TrailingData { length: trailing };
+            return Err(Box::new(reader.error(Field::TrailingData, reader.position, e)));
+        }
+
+        Ok(LazyContainer {
+            comment,
+            x,
+            y: x.wrapping_add(Y_DIFFERENCE),
+            z: x.wrapping_add(Z_DIFFERENCE),
+            entries,
+            positions,
+            cache: EntryCache::default(),
+            storage
+        })
+    }
+
+    /// Every entry's name and location, in storage order.
+    pub fn entries(&self) -> &[EntryLocation] {
+        &self.entries
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&EntryLocation> {
+        self.positions.get(name).map(|&i| &self.entries[i])
+    }
+
+    /// Reads one entry's content from storage.
+    pub fn get_file(&mut self, name: &str) -> Result<Option<File>, Box<dyn Error>> {
+        match self.positions.get(name) {
+            Some(&i) => Ok(Some(self.read_entry(i)?)),
+            None => Ok(None)
+        }
+    }
+
+    /// Reads the entry at `index` in `entries()`, from the cache if it's there.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    pub fn read_entry(&mut self, index: usize) -> Result<File, Box<dyn Error>> {
+        let location = self.entries.get(index).ok_or("entry index out of range")?;
+        if let Some(content) = self.cache.get(index) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(name = %location.name, "served from cache");
+            return Ok(File::new(location.name.clone(), content));
+        }
+
+        let length = usize::try_from(location.length)
+            .map_err(|_| FofcError::EntryTooLargeForPlatform { length: location.length })?;
+        let mut content = vec![0; length];
+        self.storage.read_at(location.offset, &mut content)?;
+
+        let file = File::new(location.name.clone(), content);
+        self.cache.insert(index, &file.content);
+        Ok(file)
+    }
+
+    /// Streams the content of the entry at `index` into `writer` a chunk at a time, so entries
+    /// too big to hold in memory can still be extracted. Bypasses the cache. Returns the number
+    /// of bytes copied.
+    pub fn copy_entry_to<W: io::Write>(&mut self, index: usize, writer: &mut W) -> Result<u64, Box<dyn Error>> {
+        let location = self.entries.get(index).ok_or("entry index out of range")?;
+        let (mut offset, end) = (location.offset, location.offset + location.length);
+        let mut chunk = vec![0; COPY_CHUNK_SIZE.min(location.length) as usize];
+
+        while offset < end {
+            let size = (chunk.len() as u64).min(end - offset) as usize;
+            self.storage.read_at(offset, &mut chunk[..size])?;
+            writer.write_all(&chunk[..size])?;
+            offset += size as u64;
+        }
+
+        Ok(location.length)
+    }
+
+    /// Keeps up to `bytes` of recently read contents in memory so repeated reads of the same
+    /// entries skip the storage. Zero, the default, disables caching.
+    pub fn set_cache_capacity(&mut self, bytes: u64) {
+        self.cache.capacity = bytes;
+        if bytes == 0 {
+            self.cache.clear();
+        } else {
+            self.cache.evict_to(bytes);
+        }
+    }
+
+    /// Total size of the contents currently cached.
+    pub fn cached_bytes(&self) -> u64 {
+        self.cache.size
+    }
+
+    /// Reads every entry into an owned `Container`.
+    pub fn into_container(mut self) -> Result<Container, Box<dyn Error>> {
+        let files = (0..self.entries.len()).map(|i| self.read_entry(i)).collect::<Result<Vec<File>, _>>()?;
+        Ok(Container::from_parts(self.comment, self.x, files))
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<u8> {
+        let mut container = Container::new("Lazy").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01, 0x02]));
+        container.add_file(File::new("b.bin", vec![0x03; 600]));
+        container.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn open_lists_without_reading_contents() {
+        let bytes = sample();
+        let mut lazy = LazyContainer::open(bytes.as_slice()).unwrap();
+        assert_eq!(lazy.comment, "Lazy");
+        assert_eq!(lazy.entries().len(), 2);
+        assert_eq!(lazy.entry("b.bin").unwrap().length, 600);
+
+        let file = lazy.get_file("a.bin").unwrap().unwrap();
+        assert_eq!(file.content, vec![0x01, 0x02]);
+        assert!(lazy.get_file("missing").unwrap().is_none());
+
+        let container = lazy.into_container().unwrap();
+        assert_eq!(container.get_file("b.bin".to_string()).unwrap().content.len(), 600);
+    }
+
+    #[test]
+    fn write_to_storage_round_trips() {
+        let container = Container::from_bytes(&sample()).unwrap();
+        let mut storage: Vec<u8> = Vec::new();
+        container.write_to(&mut storage).unwrap();
+        assert_eq!(storage, container.to_bytes().unwrap());
+
+        let path = std::env::temp_dir().join(format!("fofc-lazy-{}.fofc", std::process::id()));
+        let mut file = std::fs::File::options().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+        container.write_to(&mut file).unwrap();
+        let mut lazy = LazyContainer::open(file).unwrap();
+        assert_eq!(lazy.get_file("a.bin").unwrap().unwrap().content, vec![0x01, 0x02]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Counts reads so tests can tell when the cache was used.
+    struct CountingStorage {
+        bytes: Vec<u8>,
+        reads: usize
+    }
+
+    impl ContainerStorage for CountingStorage {
+        fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+            self.reads += 1;
+            self.bytes.read_at(offset, buf)
+        }
+
+        fn len(&mut self) -> std::io::Result<u64> {
+            Ok(self.bytes.len() as u64)
+        }
+
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+            self.bytes.write(bytes)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cache_serves_repeated_reads() {
+        let mut lazy = LazyContainer::open(CountingStorage { bytes: sample(), reads: 0 }).unwrap();
+        lazy.set_cache_capacity(600);
+
+        lazy.get_file("b.bin").unwrap();
+        let reads = lazy.storage().reads;
+        lazy.get_file("b.bin").unwrap();
+        assert_eq!(lazy.storage().reads, reads);
+        assert_eq!(lazy.cached_bytes(), 600);
+
+        // a.bin doesn't fit next to b.bin, so b.bin is evicted
+        lazy.get_file("a.bin").unwrap();
+        assert_eq!(lazy.cached_bytes(), 2);
+        lazy.get_file("b.bin").unwrap();
+        assert_eq!(lazy.storage().reads, reads + 2);
+
+        lazy.set_cache_capacity(0);
+        assert_eq!(lazy.cached_bytes(), 0);
+    }
+
+    #[test]
+    fn cache_disabled_holds_nothing() {
+        let mut cache = EntryCache::default();
+        for index in 0..10 {
+            cache.insert(index, &[]);
+        }
+        assert!(cache.entries.is_empty());
+
+        // eviction goes oldest first and keeps both maps in step
+        cache.capacity = 4;
+        cache.insert(0, &[]);
+        cache.insert(1, &[0x01, 0x02, 0x03]);
+        cache.insert(2, &[0x04, 0x05]);
+        assert!(cache.get(0).is_none());
+        assert!(cache.get(1).is_none());
+        assert_eq!(cache.get(2), Some(vec![0x04, 0x05]));
+        assert_eq!(cache.order.len(), 1);
+        assert_eq!(cache.size, 2);
+    }
+
+    #[test]
+    fn copy_entry_streams_content() {
+        let bytes = sample();
+        let mut lazy = LazyContainer::open(bytes.as_slice()).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        assert_eq!(lazy.copy_entry_to(1, &mut out).unwrap(), 600);
+        assert_eq!(out, vec![0x03; 600]);
+        assert!(lazy.copy_entry_to(2, &mut out).is_err());
+    }
+
+    #[test]
+    fn truncated_storage_is_rejected() {
+        let bytes = sample();
+        let err = LazyContainer::open(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FofcError>().map(FofcError::kind),
+            Some(FofcError::TruncatedEntry { declared: 600, remaining: 599 })
+        ));
+    }
+}
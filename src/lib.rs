@@ -1,116 +1,1164 @@
-use std::error::Error;
-use std::io::{Cursor, Read, Write};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use core::error::Error;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", feature = "wasm"))))]
 use std::time::{SystemTime, UNIX_EPOCH};
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use history::{Edit, History};
+use hooks::Observers;
+use prelude::*;
+
+#[cfg(feature = "age")]
+pub mod age;
+mod audit;
+#[cfg(feature = "bytes")]
+mod bytes_container;
+mod diff;
+mod duplicates;
+mod error;
+mod escape;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fuse")]
+pub mod fuse;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod glob;
+mod history;
+mod hooks;
+mod layout;
+mod lint;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "mac")]
+pub mod mac;
+#[cfg(feature = "signing")]
+pub mod manifest;
+mod options;
+mod overlay;
+mod patch;
+mod query;
+mod search;
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "signing")]
+pub mod signing;
+mod snapshot;
+mod sorted;
+mod stats;
+#[cfg(feature = "std")]
+mod storage;
+mod transaction;
+mod validate;
+mod view;
+#[cfg(feature = "fuse")]
+mod vfs;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use audit::{AuditAction, AuditRecord, AUDIT_ENTRY};
+#[cfg(feature = "bytes")]
+pub use bytes_container::{BytesContainer, BytesFile};
+pub use diff::{ContainerDiff, EntryChange};
+pub use duplicates::DuplicateGroup;
+pub use error::{Field, FofcError, Limit, NameProblem};
+pub use layout::{inspect_layout, LayoutReport, Region};
+pub use lint::{Lint, LintKind, Severity};
+pub use options::{CancellationToken, DuplicatePolicy, EntryOrder, LintOptions, MergeStrategy, NameDecoding, ParseMode, ParseOptions, Profile, Progress, ProgressHook, WriteOptions, source_date_epoch};
+#[cfg(feature = "hardened")]
+pub use options::HardenedOptions;
+#[cfg(feature = "std")]
+pub use lazy::{EntryLocation, LazyContainer};
+#[cfg(feature = "std")]
+pub use storage::ContainerStorage;
+pub use overlay::OverlayContainer;
+pub use patch::{apply_patch, create_patch, Patch, PatchEntry};
+pub use query::Query;
+pub use search::{GrepMatch, SearchHit, SearchIndex};
+#[cfg(feature = "std")]
+pub use shared::SharedContainer;
+pub use snapshot::Snapshot;
+pub use sorted::SortedIndex;
+pub use transaction::Transaction;
+pub use validate::ValidationReport;
+pub use stats::{ContainerStats, ExtensionStats};
+pub use view::{ContainerView, FileView, ParseWarning};
+#[cfg(feature = "chardetng")]
+pub use encoding_rs::Encoding;
+
+/// The `alloc` items `std` would otherwise bring into scope, so modules build the same
+/// with or without the standard library.
+mod prelude {
+    pub(crate) use alloc::borrow::Cow;
+    pub(crate) use alloc::boxed::Box;
+    pub(crate) use alloc::format;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec;
+    pub(crate) use alloc::vec::Vec;
+}
+
+/// Without `std` there's no `HashMap`, so name lookups fall back to a `BTreeMap`.
+#[cfg(feature = "std")]
+pub(crate) type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub(crate) type Map<K, V> = alloc::collections::BTreeMap<K, V>;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Container {
     pub comment: String,
     pub x: u64,
     pub y: u64,
     pub z: u64,
-    pub files: Vec<File>
+    /// Entries in insertion order. Name lookups notice entries added or removed here directly;
+    /// call `mark_dirty` after any other direct edit, such as renaming one in place.
+    pub files: Vec<File>,
+    /// What `add_file` does when an entry with the same name already exists.
+    pub duplicate_policy: DuplicatePolicy,
+    /// Whether `add_file` runs names through `validate_name` first.
+    pub validate_names: bool,
+    /// The order `iter` yields entries in and serialization writes them in. `files` itself
+    /// always stays in insertion order.
+    pub order: EntryOrder,
+    /// When set, `add_file`, `remove_file` and `rename_file` append a record naming this actor
+    /// to the `AUDIT_ENTRY` entry, and refuse to change that entry any other way.
+    pub audit_actor: Option<String>,
+    index: NameIndex,
+    observers: Observers,
+    /// `None` until `record_history` turns journaling on.
+    history: Option<History>,
+    dirty: bool
 }
 
-#[derive(Clone, Debug)]
+/// Containers are equal when their header fields and entries, in order, are. The duplicate
+/// policy, name validation, `order`, the audit actor, observers, edit history and the dirty flag
+/// are settings or bookkeeping rather than contents, so they aren't compared.
+impl PartialEq for Container {
+    fn eq(&self, other: &Container) -> bool {
+        self.comment == other.comment
+            && self.x == other.x
+            && self.y == other.y
+            && self.z == other.z
+            && self.files == other.files
+    }
+}
+
+impl Eq for Container {}
+
+/// An `ls -l`-style listing: a summary line with the comment and timestamp, then one line per
+/// entry with its size right-aligned, in the order set by `order`. Entries have no metadata
+/// of their own, so size and name are all there is to show.
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let layout = self.layout_stats();
+        write!(f, "{:?}: {} entries, {} bytes, timestamp {}", self.comment, self.files.len(), layout.content, self.x)?;
+
+        let width = self.files.iter().map(|file| file.content.len().to_string().len()).max().unwrap_or(0);
+        for file in self.iter() {
+            write!(f, "\n{:>width$}  {}", file.content.len(), file.name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maps each name to the position of its first entry in `Container::files`.
+#[derive(Clone, Debug, Default)]
+struct NameIndex {
+    positions: Map<String, usize>,
+    /// Length of `files` the last time the index was updated.
+    len: usize
+}
+
+impl NameIndex {
+    fn build(files: &[File]) -> NameIndex {
+        let mut index = NameIndex::default();
+        for f in files {
+            index.push(f);
+        }
+        index
+    }
+
+    fn push(&mut self, file: &File) {
+        self.positions.entry(file.name.clone()).or_insert(self.len);
+        self.len += 1;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct File {
     pub name: String,
     pub content: Vec<u8>
 }
 
+impl File {
+    pub fn new(name: impl Into<String>, content: impl Into<Vec<u8>>) -> File {
+        File {
+            name: name.into(),
+            content: content.into()
+        }
+    }
+
+    /// The content as text, if it's valid UTF-8.
+    pub fn as_str(&self) -> Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(&self.content)
+    }
+
+    /// The content as text, with invalid UTF-8 replaced by U+FFFD. Only copies if there was
+    /// something to replace.
+    pub fn as_text_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.content)
+    }
+
+    /// The content transcoded to UTF-8 from whichever encoding it looks like it's in, along
+    /// with that encoding. A byte order mark wins over the guess. Bytes that aren't valid in
+    /// the encoding are replaced by U+FFFD.
+    #[cfg(feature = "chardetng")]
+    pub fn decode_text(&self) -> (Cow<'_, str>, &'static encoding_rs::Encoding) {
+        let mut detector = chardetng::EncodingDetector::new(chardetng::Iso2022JpDetection::Deny);
+        detector.feed(&self.content, true);
+        let guess = detector.guess(None, chardetng::Utf8Detection::Allow);
+        let (text, encoding, _) = guess.decode(&self.content);
+        (text, encoding)
+    }
+}
+
+/// Byte breakdown of a serialized container, from `Container::layout_stats`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayoutStats {
+    /// The container header.
+    pub header: u64,
+    /// Every entry's name, terminator and length field.
+    pub entry_headers: u64,
+    pub content: u64
+}
+
+impl LayoutStats {
+    pub fn total(&self) -> u64 {
+        self.header + self.entry_headers + self.content
+    }
+
+    /// Bytes spent on anything but content.
+    pub fn overhead(&self) -> u64 {
+        self.header + self.entry_headers
+    }
+}
+
+/// Bytes an entry costs beyond its content: its name, the terminator and the length field.
+pub fn entry_overhead(name: &str) -> u64 {
+    name.len() as u64 + 1 + 8
+}
+
 pub const Y_DIFFERENCE: u64 = 43;
 pub const Z_DIFFERENCE: u64 = 34;
 pub const MAGIC_NUMBER: u8 = 0x46;
 
-fn read_string_until_0x00(cursor: &mut Cursor<&[u8]>) -> Result<String, Box<dyn Error>> {
-    let mut buffer: Vec<u8> = Vec::new();
+/// Checks that a name is non-empty and free of NULs and control characters, so it
+/// survives the NUL-terminated encoding and displays sanely.
+pub fn validate_name(name: &str) -> Result<(), FofcError> {
+    let problem = if name.is_empty() {
+        NameProblem::Empty
+    } else if name.contains('\0') {
+        NameProblem::ContainsNul
+    } else if name.chars().any(char::is_control) {
+        NameProblem::ControlCharacter
+    } else {
+        return Ok(());
+    };
 
-    loop {
-        let mut byte = [0; 1];
-        cursor.read_exact(&mut byte)?;
-        if byte[0] == 0x00 {
-            break;
-        }
+    Err(FofcError::InvalidName { name: name.to_string(), problem })
+}
+
+/// Somewhere the encoders can put bytes, so the format is written out in one place whether
+/// the destination grows or not.
+trait Sink {
+    fn put(&mut self, bytes: &[u8]);
+}
+
+impl Sink for Vec<u8> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// Hashing goes through the encoders too, so digests cover exactly what would be written.
+#[cfg(feature = "sha2")]
+impl Sink for sha2::Sha256 {
+    fn put(&mut self, bytes: &[u8]) {
+        sha2::Digest::update(self, bytes);
+    }
+}
+
+/// A caller's buffer that's already known to be big enough.
+struct SliceSink<'a> {
+    buf: &'a mut [u8],
+    written: usize
+}
+
+impl Sink for SliceSink<'_> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.buf[self.written..self.written + bytes.len()].copy_from_slice(bytes);
+        self.written += bytes.len();
+    }
+}
 
-        buffer.push(byte[0])
+/// Checks that a header can be read back as written and returns its entry count field. A NUL
+/// would end the comment early, and more entries than the count holds would wrap it and leave
+/// the extra entries to read back as trailing data.
+fn check_header(comment: &str, file_count: usize) -> Result<u16, FofcError> {
+    if comment.contains('\0') {
+        return Err(FofcError::CommentContainsNul);
     }
+    u16::try_from(file_count).map_err(|_| FofcError::LimitExceeded {
+        limit: Limit::FileCount,
+        value: file_count as u64,
+        max: u16::MAX as u64
+    })
+}
+
+fn encode_header(sink: &mut impl Sink, comment: &str, x: u64, file_count: u16) {
+    sink.put(&[MAGIC_NUMBER]);
+    sink.put(comment.as_bytes());
+    sink.put(&[0x00]);
+    sink.put(&x.to_le_bytes());
+    sink.put(&file_count.to_le_bytes());
+}
 
-    let string = String::from_utf8_lossy(&buffer).into_owned();
-    Ok(string)
+/// Everything of an entry that comes before its content.
+fn encode_entry_prefix(sink: &mut impl Sink, name: &str, length: u64) {
+    sink.put(name.as_bytes());
+    sink.put(&[0x00]);
+    sink.put(&length.to_le_bytes());
+}
+
+fn encode_entry(sink: &mut impl Sink, name: &str, content: &[u8]) {
+    encode_entry_prefix(sink, name, content.len() as u64);
+    sink.put(content);
+}
+
+/// Seconds since the Unix epoch. Browsers have no system clock for `SystemTime`, so the
+/// wasm build asks JavaScript instead.
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", feature = "wasm"))))]
+fn unix_time() -> Result<u64, Box<dyn Error>> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+fn unix_time() -> Result<u64, Box<dyn Error>> {
+    Ok((js_sys::Date::now() / 1000.0) as u64)
 }
 
 impl Container {
+    /// Stamps the container with the current time. Needs the `std` feature for a clock; use
+    /// `with_timestamp` without it.
+    #[cfg(feature = "std")]
     pub fn new(comment: &str) -> Result<Container, Box<dyn Error>> {
-        let x = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        Ok(Container::with_timestamp(comment, unix_time()?))
+    }
 
-        return Ok(Container {
-            comment: comment.to_string(),
+    /// Creates a container with an explicit `x` instead of reading the clock.
+    pub fn with_timestamp(comment: &str, x: u64) -> Container {
+        Container::from_parts(comment.to_string(), x, vec![])
+    }
+
+    fn from_parts(comment: String, x: u64, files: Vec<File>) -> Container {
+        let index = NameIndex::build(&files);
+
+        Container {
+            comment,
             x,
-            y: x + Y_DIFFERENCE,
-            z: x + Z_DIFFERENCE,
-            files: vec![]
-        })
+            y: x.wrapping_add(Y_DIFFERENCE),
+            z: x.wrapping_add(Z_DIFFERENCE),
+            files,
+            duplicate_policy: DuplicatePolicy::default(),
+            validate_names: false,
+            order: EntryOrder::default(),
+            audit_actor: None,
+            index,
+            observers: Observers::default(),
+            history: None,
+            dirty: false
+        }
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Container, Box<dyn Error>> {
-        let mut cursor = Cursor::new(bytes);
+        Container::from_bytes_with_options(bytes, &ParseOptions::default())
+    }
+
+    pub fn from_bytes_with_options(bytes: &[u8], options: &ParseOptions) -> Result<Container, Box<dyn Error>> {
+        let mut container = ContainerView::parse_with_options(bytes, options)?.into_owned();
+        container.duplicate_policy = options.duplicate_policy;
+        Ok(container)
+    }
+
+    /// Parses input from an untrusted source. See `HardenedOptions`.
+    #[cfg(feature = "hardened")]
+    pub fn from_untrusted(bytes: &[u8], options: &HardenedOptions) -> Result<Container, Box<dyn Error>> {
+        Container::from_bytes_with_options(bytes, options.as_parse_options())
+    }
+
+    /// Parses as much as possible, skipping entries that can't be decoded.
+    /// See `ContainerView::parse_recovering`.
+    pub fn from_bytes_recovering(bytes: &[u8], options: &ParseOptions) -> Result<(Container, Vec<ParseWarning>), Box<dyn Error>> {
+        let (view, warnings) = ContainerView::parse_recovering(bytes, options)?;
+        let mut container = view.into_owned();
+        container.duplicate_policy = options.duplicate_policy;
+        Ok((container, warnings))
+    }
+
+    /// Checks that `bytes` is a well-formed container under `options` and collects every
+    /// problem found, without copying any content. The format carries no checksums of its own;
+    /// check a sealed container with `mac::verify_mac` first.
+    pub fn validate(bytes: &[u8], options: &ParseOptions) -> ValidationReport {
+        validate::validate(bytes, options)
+    }
+
+    /// Like `validate`, but reads from storage and skips over content instead of reading it,
+    /// stopping at the first problem.
+    #[cfg(feature = "std")]
+    pub fn validate_storage<S: ContainerStorage>(storage: S, options: &ParseOptions) -> Result<ValidationReport, Box<dyn Error>> {
+        validate::validate_storage(storage, options)
+    }
+
+    /// Flags entries an ingestion service may want to refuse, such as absolute or traversing
+    /// paths and duplicate names, in entry order.
+    pub fn lint(&self, options: &LintOptions) -> Vec<Lint> {
+        lint::lint(self, options)
+    }
 
-        if cursor.read_u8()? != MAGIC_NUMBER {
-            return Err(Box::from("invalid or incorrect magic number"));
+    /// Like `lint`, but on serialized input, so entries whose stored length runs past the end
+    /// are flagged too. Only a broken header is an error.
+    pub fn lint_bytes(bytes: &[u8], options: &LintOptions) -> Result<Vec<Lint>, FofcError> {
+        lint::lint_bytes(bytes, options)
+    }
+
+    /// Adds an entry, following `duplicate_policy` if the name is already taken. With the default
+    /// `DuplicatePolicy::AllowDuplicates` nothing refuses an entry unless `validate_names` or
+    /// `audit_actor` is set; if something does, this panics where `try_add_file` would fail.
+    pub fn add_file(&mut self, file: File) {
+        if let Err(e) = self.try_add_file(file) {
+            panic!("entry refused: {e}");
         }
+    }
 
-        let comment = read_string_until_0x00(&mut cursor)?;
-        let x = cursor.read_u64::<LittleEndian>()?;
-        let y = x + Y_DIFFERENCE;
-        let z  = x + Z_DIFFERENCE;
-        let file_count = cursor.read_u16::<LittleEndian>()?;
+    /// Like `add_file`, but returning an error if the entry is refused: `FofcError::DuplicateName`
+    /// under `DuplicatePolicy::Reject`, `FofcError::InvalidName` with `validate_names` set, or
+    /// `FofcError::AuditEntry` for the audit log while auditing.
+    pub fn try_add_file(&mut self, file: File) -> Result<(), Box<dyn Error>> {
+        self.insert(file, self.duplicate_policy)?;
+        Ok(())
+    }
+
+    /// Replaces the first entry with the same name, or adds the entry if there isn't one,
+    /// whatever `duplicate_policy` says. Returns the entry that was replaced.
+    pub fn put_file(&mut self, file: File) -> Result<Option<File>, Box<dyn Error>> {
+        self.insert(file, DuplicatePolicy::ReplaceExisting)
+    }
+
+    /// Adds every entry in `files` like `try_add_file` would, but reserves room for them up front.
+    /// Stops at the first entry that's rejected, keeping the ones before it.
+    pub fn add_files(&mut self, files: impl IntoIterator<Item = File>) -> Result<(), Box<dyn Error>> {
+        let files = files.into_iter();
+        self.files.reserve(files.size_hint().0);
+
+        for file in files {
+            self.insert(file, self.duplicate_policy)?;
+        }
+        Ok(())
+    }
 
-        let mut files: Vec<File> = Vec::new();
+    /// `add_file` under `policy`.
+    fn insert(&mut self, file: File, policy: DuplicatePolicy) -> Result<Option<File>, Box<dyn Error>> {
+        if self.index.len != self.files.len() {
+            self.reindex();
+        }
+        if self.validate_names {
+            validate_name(&file.name)?;
+        }
+        if self.auditing(&file.name) {
+            return Err(Box::new(FofcError::AuditEntry));
+        }
 
-        for _ in 1..=file_count {
-            let name = read_string_until_0x00(&mut cursor)?;
-            let length = cursor.read_u64::<LittleEndian>()?;
-            let mut content = vec![0; length as usize];
-            cursor.read_exact(&mut content)?;
-            files.push(File {
-                name,
-                content
-            })
+        if policy != DuplicatePolicy::AllowDuplicates {
+            if let Some(i) = self.position(&file.name) {
+                if policy == DuplicatePolicy::Reject {
+                    return Err(Box::new(FofcError::DuplicateName { name: file.name }));
+                }
+                let replaced = core::mem::replace(&mut self.files[i], file);
+                self.journal(|files| Edit::Added { index: i, file: files[i].clone(), replaced: Some(replaced.clone()) });
+                self.dirty = true;
+                self.observers.added(&self.files[i]);
+                self.audit(AuditAction::Add, i);
+                return Ok(Some(replaced));
+            }
         }
 
+        self.index.push(&file);
+        self.files.push(file);
+        let index = self.files.len() - 1;
+        self.journal(|files| Edit::Added { index, file: files[index].clone(), replaced: None });
+        self.dirty = true;
+        self.observers.added(&self.files[index]);
+        self.audit(AuditAction::Add, index);
+        Ok(None)
+    }
 
-        Ok(Container {x, y, z, comment, files})
+    /// Whether auditing is on and `name` is the audit log, which only `audit` may change.
+    fn auditing(&self, name: &str) -> bool {
+        self.audit_actor.is_some() && name == AUDIT_ENTRY
     }
 
-    pub fn add_file(&mut self, file: File) {
-        self.files.push(file)
+    /// Appends a record of `action` on the entry at `i` to the audit log, if auditing is on.
+    fn audit(&mut self, action: AuditAction, i: usize) {
+        let name = self.files[i].name.clone();
+        self.audit_name(action, name);
+    }
+
+    fn audit_name(&mut self, action: AuditAction, name: String) {
+        let Some(actor) = self.audit_actor.clone() else {
+            return;
+        };
+        #[cfg(feature = "std")]
+        let time = unix_time().unwrap_or(0);
+        #[cfg(not(feature = "std"))]
+        let time = 0;
+        let line = AuditRecord { time, actor, action, name }.to_line();
+
+        match self.position(AUDIT_ENTRY) {
+            Some(i) => self.files[i].content.extend_from_slice(line.as_bytes()),
+            None => {
+                let log = File::new(AUDIT_ENTRY, line.into_bytes());
+                self.index.push(&log);
+                self.files.push(log);
+            }
+        }
     }
 
-    pub fn remove_file(&mut self, name: String) {
-        self.files = self.files.iter().cloned().filter(|f| f.name != name).collect()
+    /// Every record in the audit log, oldest first. Lines that don't parse are skipped.
+    pub fn audit_log(&self) -> Vec<AuditRecord> {
+        let Some(i) = self.position(AUDIT_ENTRY) else {
+            return Vec::new();
+        };
+        String::from_utf8_lossy(&self.files[i].content).lines().filter_map(AuditRecord::from_line).collect()
+    }
+
+    /// A frozen, cheaply cloned copy of the current state. Taking it copies the container once;
+    /// cloning it after that doesn't. Observers and the undo journal stay behind.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut frozen = self.clone();
+        frozen.observers = Observers::default();
+        frozen.history = None;
+        Snapshot::new(frozen)
+    }
+
+    /// Runs `edit` against a staged copy of the container and applies its changes only if it
+    /// returns `Ok`, so a failure partway through leaves the container as it was. Observers
+    /// hear about the changes once they're applied.
+    pub fn transaction<T>(&mut self, edit: impl FnOnce(&mut Transaction) -> Result<T, Box<dyn Error>>) -> Result<T, Box<dyn Error>> {
+        transaction::run(self, edit)
+    }
+
+    /// Replaces the comment, refusing one with a NUL byte since the header couldn't be read back.
+    /// The writers refuse such a comment too, if it was assigned to `comment` directly.
+    pub fn set_comment(&mut self, comment: &str) -> Result<(), FofcError> {
+        if comment.contains('\0') {
+            return Err(FofcError::CommentContainsNul);
+        }
+        self.comment = comment.to_string();
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Like `set_comment`, but also refusing comments longer than `max_length` bytes.
+    pub fn set_comment_with_limit(&mut self, comment: &str, max_length: usize) -> Result<(), FofcError> {
+        view::check_limit(Limit::CommentLength, comment.len() as u64, max_length as u64)?;
+        self.set_comment(comment)
+    }
+
+    /// Whether the comment or entries have changed through this container's methods since it
+    /// was created, loaded or saved, or since `mark_clean`. Assigning to the public fields isn't
+    /// seen; call `mark_dirty` after doing that.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Records a change made to the public fields directly, and rebuilds the name index in case
+    /// it touched `files`.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.reindex();
+    }
+
+    /// Records that the current state has been persisted elsewhere.
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Turns the undo journal on or off. While it's on, every change made through `add_file`,
+    /// `remove_file`, `rename_file` and the methods built on them can be stepped back with
+    /// `undo`, storing only the entries each change touched. Turning it off drops the journal.
+    pub fn record_history(&mut self, enabled: bool) {
+        self.history = enabled.then(History::default);
+    }
+
+    /// Adds `edit` to the journal, if it's on. Takes a closure so nothing is cloned otherwise.
+    fn journal(&mut self, edit: impl FnOnce(&[File]) -> Edit) {
+        if let Some(history) = self.history.as_mut() {
+            history.record(edit(&self.files));
+        }
+    }
+
+    /// Reverses the most recent journaled change. Returns false if there's nothing to undo.
+    /// Observers aren't told and the audit log, being append-only, keeps its record. Edits made
+    /// to `files` directly aren't journaled and can leave undo working on the wrong entries.
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.history.as_mut().and_then(|h| h.done.pop()) else {
+            return false;
+        };
+        edit.undo(&mut self.files);
+        self.dirty = true;
+        self.reindex();
+        if let Some(history) = self.history.as_mut() {
+            history.undone.push(edit);
+        }
+        true
+    }
+
+    /// Reapplies the most recently undone change. Returns false if there's nothing to redo, which
+    /// is also the case after any new change.
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.history.as_mut().and_then(|h| h.undone.pop()) else {
+            return false;
+        };
+        edit.redo(&mut self.files);
+        self.dirty = true;
+        self.reindex();
+        if let Some(history) = self.history.as_mut() {
+            history.done.push(edit);
+        }
+        true
+    }
+
+    /// Calls `hook` after every entry `add_file`, `copy_file_from` or `merge` adds or replaces.
+    /// Edits made to `files` directly aren't seen. Clones of the container share its hooks.
+    pub fn on_add(&mut self, hook: impl Fn(&File) + Send + Sync + 'static) {
+        self.observers.on_add(hook)
+    }
+
+    /// Calls `hook` with the name whenever `remove_file` removes something.
+    pub fn on_remove(&mut self, hook: impl Fn(&str) + Send + Sync + 'static) {
+        self.observers.on_remove(hook)
+    }
+
+    /// Calls `hook` with the old and new names whenever `rename_file` renames something.
+    pub fn on_rename(&mut self, hook: impl Fn(&str, &str) + Send + Sync + 'static) {
+        self.observers.on_rename(hook)
+    }
+
+    /// Removes every entry called `name`. Returns whether anything was removed.
+    pub fn remove_file(&mut self, name: String) -> bool {
+        if self.auditing(&name) {
+            return false;
+        }
+        let before = self.files.len();
+        if self.history.is_some() {
+            let entries: Vec<(usize, File)> = self.files.iter().enumerate()
+                .filter(|(_, f)| f.name == name)
+                .map(|(i, f)| (i, f.clone()))
+                .collect();
+            if !entries.is_empty() {
+                self.journal(|_| Edit::Removed { name: name.clone(), entries });
+            }
+        }
+        self.files.retain(|f| f.name != name);
+
+        let removed = self.files.len() != before;
+        if removed {
+            self.dirty = true;
+            self.reindex();
+            self.observers.removed(&name);
+            self.audit_name(AuditAction::Remove, name);
+        }
+        removed
+    }
+
+    /// Adds `source`'s entry called `name` to this container as stored, following this
+    /// container's duplicate policy. Returns false if `source` has no such entry.
+    pub fn copy_file_from(&mut self, source: &Container, name: &str) -> Result<bool, Box<dyn Error>> {
+        match source.position(name) {
+            Some(i) => {
+                self.try_add_file(source.files[i].clone())?;
+                Ok(true)
+            }
+            None => Ok(false)
+        }
+    }
+
+    /// Renames every entry called `from` to `to`. Returns whether anything was renamed.
+    pub fn rename_file(&mut self, from: &str, to: &str) -> bool {
+        if self.auditing(from) || self.auditing(to) {
+            return false;
+        }
+        let mut indices = Vec::new();
+        for (i, f) in self.files.iter_mut().enumerate().filter(|(_, f)| f.name == from) {
+            f.name = to.to_string();
+            indices.push(i);
+        }
+
+        let renamed = !indices.is_empty();
+        if renamed {
+            self.journal(|_| Edit::Renamed { from: from.to_string(), to: to.to_string(), indices });
+            self.dirty = true;
+            self.reindex();
+            self.observers.renamed(from, to);
+            self.audit_name(AuditAction::Rename { to: to.to_string() }, from.to_string());
+        }
+        renamed
     }
 
     pub fn get_file(&self, name: String) -> Option<&File> {
-        self.files.iter().find(|f| f.name == name)
+        self.position(&name).map(|i| &self.files[i])
+    }
+
+    /// Like `get_file`, but ignoring case, for archives made on case-insensitive filesystems.
+    /// Names are compared by their Unicode lowercase forms, and this scans every entry.
+    pub fn get_file_ci(&self, name: &str) -> Option<&File> {
+        fn lowercase(s: &str) -> impl Iterator<Item = char> + '_ {
+            s.chars().flat_map(char::to_lowercase)
+        }
+        self.files.iter().find(|f| lowercase(&f.name).eq(lowercase(name)))
+    }
+
+    /// Every entry called `name`, in order. Only useful with `DuplicatePolicy::AllowDuplicates`.
+    pub fn get_files<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a File> + 'a {
+        self.files.iter().filter(move |f| f.name == name)
+    }
+
+    /// Entries whose names match the glob `pattern`, such as `assets/**/*.png`. `*` and `?`
+    /// stay within one path component, `**` crosses them, and `[...]` is a character class.
+    pub fn find<'a>(&'a self, pattern: &str) -> impl Iterator<Item = &'a File> + 'a {
+        let glob = glob::Glob::new(pattern);
+        self.files.iter().filter(move |f| glob.matches(&f.name))
+    }
+
+    /// Entries whose names match the regular expression `pattern` anywhere; anchor it with
+    /// `^...$` to match whole names.
+    #[cfg(feature = "regex")]
+    pub fn find_regex<'a>(&'a self, pattern: &str) -> Result<impl Iterator<Item = &'a File> + 'a, Box<dyn Error>> {
+        let regex = regex::Regex::new(pattern)?;
+        Ok(self.files.iter().filter(move |f| regex.is_match(&f.name)))
+    }
+
+    /// Entries whose names fall in `from..to`, in name order. Sorts the names on every call;
+    /// keep a `SortedIndex` around for repeated queries.
+    pub fn files_in_range<'a>(&'a self, from: &str, to: &str) -> Vec<&'a File> {
+        SortedIndex::build(self).range(from..to).collect()
+    }
+
+    /// Groups entries that share identical content, in order of each group's first entry.
+    /// Empty entries are left out since they cost nothing.
+    pub fn find_duplicates(&self) -> Vec<DuplicateGroup<'_>> {
+        duplicates::find_duplicates(self)
+    }
+
+    /// Entry count, size figures, the `top` largest entries and a per-extension breakdown.
+    pub fn stats(&self, top: usize) -> ContainerStats<'_> {
+        stats::stats(self, top)
+    }
+
+    /// Lines containing `pattern` in every text entry, in order. Entries that aren't valid
+    /// UTF-8 are treated as binary and skipped.
+    pub fn grep<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = GrepMatch<'a>> + 'a {
+        search::grep(self, pattern)
+    }
+
+    /// Entries selected by `query`, in order.
+    pub fn query<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = &'a File> + 'a {
+        self.files.iter().filter(move |f| query.matches(f))
+    }
+
+    /// Position of the first entry called `name`.
+    fn position(&self, name: &str) -> Option<usize> {
+        // entries pushed to or removed from `files` directly change its length, so scan until the
+        // next change made through a method rebuilds the index
+        if self.index.len != self.files.len() {
+            return self.files.iter().position(|f| f.name == name);
+        }
+        self.index.positions.get(name).copied().filter(|&i| self.files[i].name == name)
+    }
+
+    /// Whether both containers have the same comment and the same entries, ignoring their
+    /// timestamps and the order of the entries.
+    pub fn content_eq(&self, other: &Container) -> bool {
+        fn sorted(container: &Container) -> Vec<(&str, &[u8])> {
+            let mut entries: Vec<(&str, &[u8])> = container.files.iter().map(|f| (f.name.as_str(), &f.content[..])).collect();
+            entries.sort_unstable();
+            entries
+        }
+
+        self.comment == other.comment && self.files.len() == other.files.len() && sorted(self) == sorted(other)
+    }
+
+    /// What changed going from this container to `other`.
+    pub fn diff(&self, other: &Container) -> ContainerDiff {
+        diff::diff(self, other)
+    }
+
+    /// Adds every entry of `other`. Entries whose name and content we already have are
+    /// skipped; same-named entries with different content are settled by `strategy`.
+    pub fn merge(&mut self, other: &Container, strategy: MergeStrategy) -> Result<(), Box<dyn Error>> {
+        if strategy == MergeStrategy::ErrorOnConflict {
+            if let Some(f) = other.files.iter().find(|f| self.position(&f.name).is_some_and(|i| self.files[i].content != f.content)) {
+                return Err(Box::new(FofcError::DuplicateName { name: f.name.clone() }));
+            }
+        }
+
+        // this container's audit log records the merge itself, so the other one's isn't taken
+        let auditing = self.audit_actor.is_some();
+        for f in other.files.iter().filter(|f| !(auditing && f.name == AUDIT_ENTRY)) {
+            let Some(i) = self.position(&f.name) else {
+                self.try_add_file(f.clone())?;
+                continue;
+            };
+            if self.files[i].content == f.content {
+                continue;
+            }
+
+            match strategy {
+                MergeStrategy::TheirsWins => {
+                    let replaced = self.files[i].clone();
+                    self.files[i].content = f.content.clone();
+                    self.journal(|files| Edit::Added { index: i, file: files[i].clone(), replaced: Some(replaced) });
+                    self.dirty = true;
+                    self.observers.added(&self.files[i]);
+                    self.audit(AuditAction::Add, i);
+                }
+                MergeStrategy::OursWins | MergeStrategy::ErrorOnConflict => {}
+                MergeStrategy::KeepBoth => {
+                    let name = self.free_name(&f.name);
+                    self.try_add_file(File::new(name, f.content.clone()))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `name` with the first `-N` suffix, before any extension, that no entry uses.
+    fn free_name(&self, name: &str) -> String {
+        let (stem, extension) = match name.rfind('.') {
+            Some(dot) if dot > 0 && !name[dot..].contains('/') => name.split_at(dot),
+            _ => (name, "")
+        };
+
+        (1..).map(|n| format!("{stem}-{n}{extension}"))
+            .find(|candidate| self.position(candidate).is_none())
+            .unwrap_or_default()
+    }
+
+    /// A SHA-256 digest identifying the container by its contents: the comment and entries,
+    /// taken in name order, with the timestamp left out. Containers that are `content_eq`
+    /// and share their comment have the same id.
+    #[cfg(feature = "sha2")]
+    pub fn container_id(&self) -> [u8; 32] {
+        let mut files: Vec<&File> = self.files.iter().collect();
+        EntryOrder::Name.sort(&mut files);
+
+        let mut hasher = sha2::Sha256::default();
+        // the id only has to tell contents apart, and every entry is hashed whatever the count says
+        encode_header(&mut hasher, &self.comment, 0, u16::try_from(files.len()).unwrap_or(u16::MAX));
+        for f in files {
+            encode_entry(&mut hasher, &f.name, &f.content);
+        }
+        sha2::Digest::finalize(hasher).into()
+    }
+
+    /// Puts the entries in the same order `EntryOrder::Name` writes them, so containers with the
+    /// same entries serialize to the same bytes whichever tool built them. Names are already
+    /// UTF-8 and entries carry no other metadata, so order is all there is to normalize.
+    pub fn canonicalize(&mut self) {
+        self.files.sort_by(|a, b| a.name.cmp(&b.name));
+        self.dirty = true;
+        self.reindex();
+    }
+
+    /// Every entry, in the order set by `order`.
+    pub fn iter(&self) -> impl Iterator<Item = &File> {
+        let mut files: Vec<&File> = self.files.iter().collect();
+        self.order.sort(&mut files);
+        files.into_iter()
+    }
+
+    /// Rebuilds the name index.
+    pub(crate) fn reindex(&mut self) {
+        self.index = NameIndex::build(&self.files)
+    }
+
+    /// Number of entries, duplicates included.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Combined length of every entry's content, without any framing.
+    pub fn total_content_size(&self) -> u64 {
+        self.files.iter().map(|f| f.content.len() as u64).sum()
+    }
+
+    /// Estimated heap memory the container holds on to, in bytes: its allocations' capacities
+    /// plus the name index. Allocator bookkeeping isn't counted.
+    pub fn heap_size(&self) -> usize {
+        let files: usize = self.files.iter().map(|f| f.name.capacity() + f.content.capacity()).sum();
+        let index: usize = self.index.positions.keys()
+            .map(|name| name.capacity() + core::mem::size_of::<(String, usize)>())
+            .sum();
+
+        self.comment.capacity()
+            + self.audit_actor.as_ref().map_or(0, String::capacity)
+            + self.files.capacity() * core::mem::size_of::<File>()
+            + files
+            + index
+    }
+
+    fn header_size(&self) -> u64 {
+        // magic + comment + terminator + x + file count
+        1 + self.comment.len() as u64 + 1 + 8 + 2
+    }
+
+    /// Number of bytes `to_bytes` will produce for this container.
+    pub fn serialized_size(&self) -> u64 {
+        self.layout_stats().total()
+    }
+
+    /// How the serialized size splits between framing and content. The format has no padding
+    /// and no dead records, so everything that isn't content is a header.
+    pub fn layout_stats(&self) -> LayoutStats {
+        LayoutStats {
+            header: self.header_size(),
+            entry_headers: self.files.iter().map(|f| entry_overhead(&f.name)).sum(),
+            content: self.total_content_size()
+        }
     }
 
     pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        let mut bytes: Vec<u8> = Vec::new();
-        bytes.push(MAGIC_NUMBER);
-        bytes.write(self.comment.as_bytes())?;
-        bytes.push(0x00);
-        bytes.write_u64::<LittleEndian>(self.x)?;
-        bytes.write_u16::<LittleEndian>(self.files.len() as u16)?;
+        self.to_bytes_with_options(&WriteOptions::default())
+    }
 
-        for f in self.files.iter() {
-            bytes.write(f.name.as_bytes())?;
-            bytes.push(0x00);
-            bytes.write_u64::<LittleEndian>(f.content.len() as u64)?;
-            bytes.write_all(f.content.as_slice())?;
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(files = self.files.len())))]
+    pub fn to_bytes_with_options(&self, options: &WriteOptions) -> Result<Vec<u8>, Box<dyn Error>> {
+        let files = self.files_to_write(options)?;
+        let total_bytes = self.written_size(&files);
+        let mut bytes: Vec<u8> = Vec::with_capacity(usize::try_from(total_bytes)?);
+        encode_header(&mut bytes, &self.comment, options.timestamp.unwrap_or(self.x), check_header(&self.comment, files.len())?);
+
+        for (entry, f) in files.iter().enumerate() {
+            options.check_cancelled()?;
+            encode_entry(&mut bytes, &f.name, &f.content);
+            options.report_progress(&Progress {
+                entry,
+                entry_count: files.len(),
+                entry_name: &f.name,
+                bytes_processed: bytes.len() as u64,
+                total_bytes
+            });
         }
 
         Ok(bytes)
     }
+
+    /// Bytes the header and `files` take up once written.
+    fn written_size(&self, files: &Entries<'_>) -> u64 {
+        self.header_size() + files.iter().map(|f| entry_overhead(&f.name) + f.content.len() as u64).sum::<u64>()
+    }
+
+    /// The entries `options` says to write. `files` is used as it stands unless duplicates have
+    /// to be settled or the entries sorted, so the default options don't allocate.
+    fn files_to_write(&self, options: &WriteOptions) -> Result<Entries<'_>, FofcError> {
+        let order = options.order.unwrap_or(self.order);
+        if options.duplicate_policy == DuplicatePolicy::AllowDuplicates && order == EntryOrder::Insertion {
+            return Ok(Entries::All(&self.files));
+        }
+
+        let mut files: Vec<&File> = Vec::with_capacity(self.files.len());
+        let mut positions: Map<&str, usize> = Map::new();
+        for f in self.files.iter() {
+            match (positions.get(f.name.as_str()), options.duplicate_policy) {
+                (Some(_), DuplicatePolicy::Reject) => return Err(FofcError::DuplicateName { name: f.name.clone() }),
+                (Some(&i), DuplicatePolicy::ReplaceExisting) => files[i] = f,
+                _ => {
+                    positions.entry(&f.name).or_insert(files.len());
+                    files.push(f);
+                }
+            }
+        }
+
+        order.sort(&mut files);
+        Ok(Entries::Selected(files))
+    }
+
+    /// Serializes into `buf` without allocating and returns the number of bytes written.
+    /// Entries follow `order`, like `to_bytes`, and sorting them by anything but insertion
+    /// needs a list of them, which is the one allocation then. Fails with `FofcError::BufferTooSmall`, leaving `buf` untouched, if it's shorter than
+    /// `serialized_size()`.
+    pub fn write_to_slice(&self, buf: &mut [u8]) -> Result<usize, FofcError> {
+        let files = self.files_to_write(&WriteOptions::default())?;
+        let file_count = check_header(&self.comment, files.len())?;
+        let needed = self.serialized_size();
+        if needed > buf.len() as u64 {
+            return Err(FofcError::BufferTooSmall { needed, available: buf.len() as u64 });
+        }
+
+        let mut sink = SliceSink { buf, written: 0 };
+        encode_header(&mut sink, &self.comment, self.x, file_count);
+        for f in files.iter() {
+            encode_entry(&mut sink, &f.name, &f.content);
+        }
+
+        Ok(sink.written)
+    }
+
+    /// Appends the serialized container to `storage` one entry at a time, in the order set by
+    /// `order`, then flushes it.
+    #[cfg(feature = "std")]
+    pub fn write_to<S: ContainerStorage>(&self, storage: &mut S) -> Result<(), Box<dyn Error>> {
+        self.write_to_with_options(storage, &WriteOptions::default())
+    }
+
+    /// Like `write_to`, under `options`. A cancelled write stops between entries, leaving what
+    /// was already appended in `storage`.
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(files = self.files.len())))]
+    pub fn write_to_with_options<S: ContainerStorage>(&self, storage: &mut S, options: &WriteOptions) -> Result<(), Box<dyn Error>> {
+        let files = self.files_to_write(options)?;
+        let total_bytes = self.written_size(&files);
+        let mut header: Vec<u8> = Vec::new();
+        encode_header(&mut header, &self.comment, options.timestamp.unwrap_or(self.x), check_header(&self.comment, files.len())?);
+        storage.write(&header)?;
+        let mut written = header.len() as u64;
+
+        for (entry, f) in files.iter().enumerate() {
+            options.check_cancelled()?;
+            header.clear();
+            encode_entry_prefix(&mut header, &f.name, f.content.len() as u64);
+            storage.write(&header)?;
+            storage.write(&f.content)?;
+            written += header.len() as u64 + f.content.len() as u64;
+            options.report_progress(&Progress {
+                entry,
+                entry_count: files.len(),
+                entry_name: &f.name,
+                bytes_processed: written,
+                total_bytes
+            });
+        }
+
+        storage.flush()?;
+        Ok(())
+    }
+
+    /// Writes the container to the file at `path`, replacing what was there. The bytes go to a
+    /// temporary file beside it, which is synced and then renamed over `path`, so a save that
+    /// fails partway leaves the old file as it was. Holds an exclusive advisory lock on `path`
+    /// throughout and fails with `FofcError::WouldBlock` instead of waiting if another handle
+    /// has the file locked. Marks the container clean once the file is in place.
+    #[cfg(feature = "std")]
+    pub fn save(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let path = path.as_ref();
+        let target = fs::OpenOptions::new().write(true).create(true).truncate(false).open(path)?;
+        try_lock(target.try_lock())?;
+
+        let temp = temp_path(path);
+        let result = self.save_to(&temp, path);
+        match result {
+            Ok(()) => self.dirty = false,
+            Err(_) => {
+                let _ = fs::remove_file(&temp);
+            }
+        }
+        result
+    }
+
+    #[cfg(feature = "std")]
+    fn save_to(&self, temp: &Path, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut file = fs::File::create(temp)?;
+        self.write_to(&mut file)?;
+        file.sync_all()?;
+        fs::rename(temp, path)?;
+        // the rename is only durable once the directory holding it is synced too
+        #[cfg(unix)]
+        fs::File::open(path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new(".")))?.sync_all()?;
+        Ok(())
+    }
+
+    /// Like `save`, but only if the container has changed since it was created, loaded or last
+    /// saved. Returns whether it was written.
+    #[cfg(feature = "std")]
+    pub fn save_if_dirty(&mut self, path: impl AsRef<Path>) -> Result<bool, Box<dyn Error>> {
+        if !self.dirty {
+            return Ok(false);
+        }
+        self.save(path)?;
+        Ok(true)
+    }
+
+    /// Reads the container in the file at `path` under a shared advisory lock, failing with
+    /// `FofcError::WouldBlock` if a save holds it.
+    #[cfg(feature = "std")]
+    pub fn open(path: impl AsRef<Path>) -> Result<Container, Box<dyn Error>> {
+        let mut file = fs::File::open(path)?;
+        try_lock(file.try_lock_shared())?;
+        let mut bytes = Vec::new();
+        std::io::Read::read_to_end(&mut file, &mut bytes)?;
+        Container::from_bytes(&bytes)
+    }
+}
+
+/// The entries a writer puts out, in order.
+enum Entries<'a> {
+    /// `Container::files` as it stands.
+    All(&'a [File]),
+    Selected(Vec<&'a File>)
+}
+
+impl<'a> Entries<'a> {
+    fn len(&self) -> usize {
+        match self {
+            Entries::All(files) => files.len(),
+            Entries::Selected(files) => files.len()
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &'a File> + '_ {
+        let (all, selected): (&'a [File], &[&'a File]) = match self {
+            Entries::All(files) => (files, &[]),
+            Entries::Selected(files) => (&[], files)
+        };
+        all.iter().chain(selected.iter().copied())
+    }
+}
+
+/// A name beside `path` that no other save is using, for `Container::save` to write to.
+#[cfg(feature = "std")]
+fn temp_path(path: &Path) -> std::path::PathBuf {
+    static NEXT: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(0);
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}-{}.tmp", std::process::id(), NEXT.fetch_add(1, core::sync::atomic::Ordering::Relaxed)));
+    path.with_file_name(name)
+}
+
+#[cfg(feature = "std")]
+fn try_lock(result: Result<(), fs::TryLockError>) -> Result<(), Box<dyn Error>> {
+    match result {
+        Ok(()) => Ok(()),
+        Err(fs::TryLockError::WouldBlock) => Err(Box::new(FofcError::WouldBlock)),
+        Err(fs::TryLockError::Error(e)) => Err(Box::new(e))
+    }
 }
 
 
@@ -136,8 +1184,17 @@ mod tests {
         container.add_file(file);
         assert_eq!(container.files.len(), 1);
 
-        container.remove_file(file_name);
+        assert!(container.remove_file(file_name.clone()));
         assert_eq!(container.files.len(), 0);
+        assert!(!container.remove_file(file_name));
+    }
+
+    #[test]
+    fn with_timestamp_skips_the_clock() {
+        let container = Container::with_timestamp("Fixed", 1_000);
+        assert_eq!(container.x, 1_000);
+        assert_eq!(container.y, 1_000 + Y_DIFFERENCE);
+        assert_eq!(container.z, 1_000 + Z_DIFFERENCE);
     }
 
     #[test]
@@ -152,9 +1209,626 @@ mod tests {
         container.add_file(file2);
 
         let as_bytes = container.to_bytes().unwrap();
+        assert_eq!(as_bytes.len() as u64, container.serialized_size());
 
         let new_container = Container::from_bytes(as_bytes.as_slice()).unwrap();
 
         println!("{:?}", new_container.files);
     }
+
+    #[test]
+    fn duplicate_policy_applies_on_add() {
+        let mut container = Container::new("Duplicates").unwrap();
+        container.add_file(File::new("a.txt", vec![0x01]));
+        container.add_file(File::new("a.txt", vec![0x02]));
+        assert_eq!(container.get_files("a.txt").count(), 2);
+        assert_eq!(container.get_file("a.txt".to_string()).unwrap().content, vec![0x01]);
+
+        container.duplicate_policy = DuplicatePolicy::Reject;
+        let err = container.try_add_file(File::new("a.txt", vec![0x03])).unwrap_err();
+        assert!(matches!(err.downcast_ref::<FofcError>(), Some(FofcError::DuplicateName { .. })));
+
+        container.duplicate_policy = DuplicatePolicy::ReplaceExisting;
+        container.add_file(File::new("a.txt", vec![0x04]));
+        assert_eq!(container.files.len(), 2);
+        assert_eq!(container.get_file("a.txt".to_string()).unwrap().content, vec![0x04]);
+    }
+
+    #[test]
+    #[should_panic(expected = "entry refused")]
+    fn add_file_panics_where_try_add_file_fails() {
+        let mut container = Container::with_timestamp("Duplicates", 0);
+        container.duplicate_policy = DuplicatePolicy::Reject;
+        container.add_file(File::new("a.txt", vec![]));
+        container.add_file(File::new("a.txt", vec![]));
+    }
+
+    #[test]
+    fn names_are_validated_on_request() {
+        let mut container = Container::new("Names").unwrap();
+        container.add_file(File::new("bad\0name", vec![]));
+
+        container.validate_names = true;
+        for (name, problem) in [("", NameProblem::Empty), ("a\0b", NameProblem::ContainsNul), ("a\nb", NameProblem::ControlCharacter)] {
+            let err = container.try_add_file(File::new(name, vec![])).unwrap_err();
+            match err.downcast_ref::<FofcError>() {
+                Some(FofcError::InvalidName { problem: p, .. }) => assert_eq!(*p, problem),
+                other => panic!("unexpected error {other:?}")
+            }
+        }
+        container.add_file(File::new("assets/ok.png", vec![]));
+        assert_eq!(container.files.len(), 2);
+    }
+
+    #[test]
+    fn lookups_follow_mutations() {
+        let mut container = Container::new("Lookups").unwrap();
+        container.add_file(File::new("a.txt", vec![0x01]));
+        container.add_file(File::new("b.txt", vec![0x02]));
+        assert_eq!(container.get_file("b.txt".to_string()).unwrap().content, vec![0x02]);
+
+        assert!(container.rename_file("b.txt", "c.txt"));
+        assert!(container.get_file("b.txt".to_string()).is_none());
+        assert_eq!(container.get_file("c.txt".to_string()).unwrap().content, vec![0x02]);
+
+        container.remove_file("a.txt".to_string());
+        assert_eq!(container.get_file("c.txt".to_string()).unwrap().content, vec![0x02]);
+
+        // edits made straight to `files` are still found
+        container.files.push(File::new("d.txt", vec![0x03]));
+        assert_eq!(container.get_file("d.txt".to_string()).unwrap().content, vec![0x03]);
+
+        // and ones that leave the length alone once `mark_dirty` says so
+        container.files[0].name = "z.txt".to_string();
+        container.mark_dirty();
+        assert_eq!(container.get_file("z.txt".to_string()).unwrap().content, vec![0x02]);
+        let d = container.files.remove(1);
+        container.files.push(File::new("e.txt", d.content));
+        container.mark_dirty();
+        assert!(container.get_file("d.txt".to_string()).is_none());
+        assert_eq!(container.get_file("e.txt".to_string()).unwrap().content, vec![0x03]);
+
+        container.duplicate_policy = DuplicatePolicy::Reject;
+        assert!(container.try_add_file(File::new("e.txt", vec![])).is_err());
+        assert!(container.try_add_file(File::new("z.txt", vec![])).is_err());
+        assert_eq!(container.files.len(), 2);
+    }
+
+    #[test]
+    fn write_to_slice_matches_to_bytes() {
+        let mut container = Container::with_timestamp("Slice", 7);
+        container.add_file(File::new("a.bin", vec![0x01, 0x02, 0x03]));
+        let expected = container.to_bytes().unwrap();
+
+        let mut buf = [0xff; 64];
+        let written = container.write_to_slice(&mut buf).unwrap();
+        assert_eq!(&buf[..written], &expected[..]);
+
+        let mut short = [0xff; 8];
+        let e = container.write_to_slice(&mut short).unwrap_err();
+        assert!(matches!(e, FofcError::BufferTooSmall { needed, available: 8 } if needed == expected.len() as u64));
+        assert_eq!(short, [0xff; 8]);
+
+        // every writer follows `order`, and only builds a list of entries when it has to
+        container.add_file(File::new("0.bin", vec![0x04]));
+        assert!(matches!(container.files_to_write(&WriteOptions::default()), Ok(Entries::All(_))));
+        container.order = EntryOrder::Name;
+        assert!(matches!(container.files_to_write(&WriteOptions::default()), Ok(Entries::Selected(_))));
+        let expected = container.to_bytes().unwrap();
+        let written = container.write_to_slice(&mut buf).unwrap();
+        assert_eq!(&buf[..written], &expected[..]);
+        let mut storage: Vec<u8> = Vec::new();
+        container.write_to(&mut storage).unwrap();
+        assert_eq!(storage, expected);
+        assert_eq!(ContainerView::parse(&expected).unwrap().files[0].name, "0.bin");
+    }
+
+    #[test]
+    fn writes_can_be_cancelled() {
+        let mut container = Container::with_timestamp("Cancel", 0);
+        container.add_file(File::new("a.bin", vec![0x01]));
+        assert!(container.to_bytes_with_options(&WriteOptions { cancel: Some(CancellationToken::new()), ..WriteOptions::default() }).is_ok());
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let options = WriteOptions { cancel: Some(token), ..WriteOptions::default() };
+        let e = container.to_bytes_with_options(&options).unwrap_err();
+        assert!(matches!(e.downcast_ref::<FofcError>(), Some(FofcError::Cancelled)));
+        let e = container.write_to_with_options(&mut Vec::new(), &options).unwrap_err();
+        assert!(matches!(e.downcast_ref::<FofcError>(), Some(FofcError::Cancelled)));
+    }
+
+    #[test]
+    fn writes_report_progress_per_entry() {
+        use std::sync::{Arc, Mutex};
+
+        let mut container = Container::with_timestamp("Progress", 0);
+        container.add_file(File::new("a.bin", vec![0x01]));
+        container.add_file(File::new("b.bin", vec![0x02, 0x03]));
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let options = WriteOptions {
+            on_progress: Some(ProgressHook::new(move |p| {
+                log.lock().unwrap().push((p.entry, p.entry_name.to_string(), p.bytes_processed, p.total_bytes))
+            })),
+            ..WriteOptions::default()
+        };
+        let bytes = container.to_bytes_with_options(&options).unwrap();
+        let mut storage: Vec<u8> = Vec::new();
+        container.write_to_with_options(&mut storage, &options).unwrap();
+        assert_eq!(storage, bytes);
+
+        let total = bytes.len() as u64;
+        let expected = [(0, "a.bin".to_string(), total - 16, total), (1, "b.bin".to_string(), total, total)];
+        assert_eq!(*seen.lock().unwrap(), [expected.clone(), expected].concat());
+    }
+
+    #[test]
+    fn copy_file_between_containers() {
+        let mut source = Container::with_timestamp("Source", 0);
+        source.add_file(File::new("a.bin", vec![0x01, 0x02]));
+
+        let mut destination = Container::with_timestamp("Destination", 0);
+        destination.duplicate_policy = DuplicatePolicy::Reject;
+        assert!(destination.copy_file_from(&source, "a.bin").unwrap());
+        assert!(!destination.copy_file_from(&source, "b.bin").unwrap());
+        assert!(destination.copy_file_from(&source, "a.bin").is_err());
+        assert_eq!(destination.get_file("a.bin".to_string()).unwrap().content, vec![0x01, 0x02]);
+    }
+
+    #[test]
+    fn layout_stats_add_up() {
+        let mut container = Container::with_timestamp("Stats", 0);
+        container.add_file(File::new("a.bin", vec![0x01; 10]));
+        container.add_file(File::new("bc.bin", vec![0x02; 20]));
+
+        let stats = container.layout_stats();
+        assert_eq!(stats.header, 17);
+        assert_eq!(stats.entry_headers, entry_overhead("a.bin") + entry_overhead("bc.bin"));
+        assert_eq!(stats.content, 30);
+        assert_eq!(stats.total(), container.to_bytes().unwrap().len() as u64);
+    }
+
+    #[test]
+    fn find_matches_globs() {
+        let mut container = Container::with_timestamp("Find", 0);
+        container.add_file(File::new("assets/ui/a.png", vec![0x01]));
+        container.add_file(File::new("assets/b.png", vec![0x02]));
+        container.add_file(File::new("assets/c.txt", vec![0x03]));
+
+        let names: Vec<&str> = container.find("assets/**/*.png").map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["assets/ui/a.png", "assets/b.png"]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn find_regex_matches_names() {
+        let mut container = Container::with_timestamp("Regex", 0);
+        container.add_file(File::new("run.exe", vec![0x01]));
+        container.add_file(File::new("readme.txt", vec![0x02]));
+
+        let names: Vec<&str> = container.find_regex(r"\.(exe|dll)$").unwrap().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["run.exe"]);
+        assert!(container.find_regex("(").is_err());
+    }
+
+    #[test]
+    fn case_insensitive_lookup() {
+        let mut container = Container::with_timestamp("Case", 0);
+        container.add_file(File::new("Textures/Ground.PNG", vec![0x01]));
+        container.add_file(File::new("ÄRGER.txt", vec![0x02]));
+
+        assert_eq!(container.get_file_ci("textures/ground.png").unwrap().content, vec![0x01]);
+        assert_eq!(container.get_file_ci("ärger.TXT").unwrap().content, vec![0x02]);
+        assert!(container.get_file_ci("textures/ground.jpg").is_none());
+    }
+
+    #[test]
+    fn deterministic_output_ignores_insertion_order_and_clock() {
+        let mut first = Container::new("Build").unwrap();
+        first.add_file(File::new("b.txt", vec![0x02]));
+        first.add_file(File::new("a.txt", vec![0x01]));
+
+        let mut second = Container::with_timestamp("Build", 12345);
+        second.add_file(File::new("a.txt", vec![0x01]));
+        second.add_file(File::new("b.txt", vec![0x02]));
+
+        let options = WriteOptions::deterministic();
+        let bytes = first.to_bytes_with_options(&options).unwrap();
+        assert_eq!(bytes, second.to_bytes_with_options(&options).unwrap());
+
+        let parsed = Container::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.x, source_date_epoch().unwrap_or(0));
+        assert_eq!(parsed.files[0].name, "a.txt");
+    }
+
+    #[test]
+    fn canonicalized_containers_compare_byte_for_byte() {
+        let mut first = Container::with_timestamp("Tool A", 1);
+        first.add_file(File::new("b.txt", vec![0x02]));
+        first.add_file(File::new("a.txt", vec![0x01]));
+
+        let mut second = Container::with_timestamp("Tool A", 1);
+        second.add_file(File::new("a.txt", vec![0x01]));
+        second.add_file(File::new("b.txt", vec![0x02]));
+
+        first.canonicalize();
+        assert_eq!(first.to_bytes().unwrap(), second.to_bytes().unwrap());
+        assert_eq!(first.get_file("b.txt".to_string()).unwrap().content, vec![0x02]);
+    }
+
+    #[test]
+    fn equality_ignores_the_index() {
+        let mut first = Container::with_timestamp("Eq", 1);
+        first.add_file(File::new("a.txt", vec![0x01]));
+        first.add_file(File::new("b.txt", vec![0x02]));
+        let parsed = Container::from_bytes(&first.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, first);
+
+        let mut second = Container::with_timestamp("Eq", 2);
+        second.add_file(File::new("b.txt", vec![0x02]));
+        second.add_file(File::new("a.txt", vec![0x01]));
+        assert_ne!(first, second);
+        assert!(first.content_eq(&second));
+
+        second.files[0] = File::new("b.txt", vec![0x03]);
+        assert!(!first.content_eq(&second));
+    }
+
+    #[test]
+    fn merge_strategies_settle_conflicts() {
+        let mut ours = Container::with_timestamp("Ours", 0);
+        ours.add_file(File::new("shared.txt", vec![0x01]));
+        ours.add_file(File::new("same.txt", vec![0x05]));
+        ours.add_file(File::new("shared-1.txt", vec![0x06]));
+
+        let mut theirs = Container::with_timestamp("Theirs", 0);
+        theirs.add_file(File::new("shared.txt", vec![0x02]));
+        theirs.add_file(File::new("same.txt", vec![0x05]));
+        theirs.add_file(File::new("new.txt", vec![0x03]));
+
+        let mut merged = ours.clone();
+        let e = merged.merge(&theirs, MergeStrategy::ErrorOnConflict).unwrap_err();
+        assert!(matches!(e.downcast_ref::<FofcError>(), Some(FofcError::DuplicateName { name }) if name == "shared.txt"));
+        assert_eq!(merged, ours);
+
+        merged.merge(&theirs, MergeStrategy::OursWins).unwrap();
+        assert_eq!(merged.get_file("shared.txt".to_string()).unwrap().content, vec![0x01]);
+        assert_eq!(merged.files.len(), 4);
+
+        let mut merged = ours.clone();
+        merged.merge(&theirs, MergeStrategy::TheirsWins).unwrap();
+        assert_eq!(merged.get_file("shared.txt".to_string()).unwrap().content, vec![0x02]);
+
+        let mut merged = ours.clone();
+        merged.merge(&theirs, MergeStrategy::KeepBoth).unwrap();
+        assert_eq!(merged.get_file("shared.txt".to_string()).unwrap().content, vec![0x01]);
+        assert_eq!(merged.get_file("shared-2.txt".to_string()).unwrap().content, vec![0x02]);
+        assert_eq!(merged.files.len(), 5);
+    }
+
+    #[test]
+    fn duplicates_are_normalized_on_write() {
+        let mut container = Container::with_timestamp("Dups", 0);
+        container.add_file(File::new("a.txt", vec![0x01]));
+        container.add_file(File::new("b.txt", vec![0x02]));
+        container.add_file(File::new("a.txt", vec![0x03]));
+
+        let mut options = WriteOptions { duplicate_policy: DuplicatePolicy::Reject, ..WriteOptions::default() };
+        let e = container.to_bytes_with_options(&options).unwrap_err();
+        assert!(matches!(e.downcast_ref::<FofcError>(), Some(FofcError::DuplicateName { name }) if name == "a.txt"));
+
+        options.duplicate_policy = DuplicatePolicy::ReplaceExisting;
+        let parsed = Container::from_bytes(&container.to_bytes_with_options(&options).unwrap()).unwrap();
+        assert_eq!(parsed.files, [File::new("a.txt", vec![0x03]), File::new("b.txt", vec![0x02])]);
+    }
+
+    #[test]
+    fn order_policy_governs_iteration_and_layout() {
+        let mut container = Container::with_timestamp("Order", 0);
+        container.add_file(File::new("b.txt", vec![0x01; 3]));
+        container.add_file(File::new("c.txt", vec![0x01; 1]));
+        container.add_file(File::new("a.txt", vec![0x01; 2]));
+
+        let names = |c: &Container| c.iter().map(|f| f.name.clone()).collect::<Vec<String>>();
+        assert_eq!(names(&container), ["b.txt", "c.txt", "a.txt"]);
+
+        container.order = EntryOrder::Size;
+        assert_eq!(names(&container), ["c.txt", "a.txt", "b.txt"]);
+        let parsed = Container::from_bytes(&container.to_bytes().unwrap()).unwrap();
+        assert_eq!(names(&parsed), ["c.txt", "a.txt", "b.txt"]);
+
+        container.order = EntryOrder::Name;
+        assert_eq!(names(&container), ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(container.files[0].name, "b.txt");
+    }
+
+    #[test]
+    fn profiles_bundle_write_options() {
+        let mut container = Container::with_timestamp("Profile", 42);
+        container.add_file(File::new("b.txt", vec![0x02]));
+        container.add_file(File::new("a.txt", vec![0x01]));
+
+        let archived = container.to_bytes_with_options(&Profile::ArchivalMax.write_options()).unwrap();
+        let parsed = Container::from_bytes(&archived).unwrap();
+        assert_eq!((parsed.x, parsed.files[0].name.as_str()), (42, "a.txt"));
+        assert_eq!(container.to_bytes_with_options(&Profile::FastDev.write_options()).unwrap(), container.to_bytes().unwrap());
+
+        container.add_file(File::new("a.txt", vec![0x03]));
+        assert!(container.to_bytes_with_options(&Profile::Reproducible.write_options()).is_err());
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn container_id_ignores_volatile_fields() {
+        let mut first = Container::with_timestamp("Id", 1);
+        first.add_file(File::new("a.txt", vec![0x01]));
+        first.add_file(File::new("b.txt", vec![0x02]));
+
+        let mut second = Container::with_timestamp("Id", 2);
+        second.add_file(File::new("b.txt", vec![0x02]));
+        second.add_file(File::new("a.txt", vec![0x01]));
+        assert_eq!(first.container_id(), second.container_id());
+
+        second.add_file(File::new("c.txt", vec![]));
+        assert_ne!(first.container_id(), second.container_id());
+    }
+
+    #[test]
+    fn display_lists_entries() {
+        let mut container = Container::with_timestamp("Listing", 7);
+        container.add_file(File::new("small", vec![0x00; 5]));
+        container.add_file(File::new("dir/big", vec![0x00; 1200]));
+
+        assert_eq!(container.to_string(), "\"Listing\": 2 entries, 1205 bytes, timestamp 7\n   5  small\n1200  dir/big");
+        assert_eq!(Container::with_timestamp("", 0).to_string(), "\"\": 0 entries, 0 bytes, timestamp 0");
+    }
+
+    #[test]
+    fn observers_see_mutations() {
+        use std::sync::{Arc, Mutex};
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut container = Container::with_timestamp("Hooks", 0);
+        let log = events.clone();
+        container.on_add(move |f| log.lock().unwrap().push(format!("add {}", f.name)));
+        let log = events.clone();
+        container.on_remove(move |name| log.lock().unwrap().push(format!("remove {name}")));
+        let log = events.clone();
+        container.on_rename(move |from, to| log.lock().unwrap().push(format!("rename {from} {to}")));
+
+        container.add_file(File::new("a", vec![]));
+        container.rename_file("a", "b");
+        container.rename_file("missing", "c");
+        container.remove_file("b".to_string());
+        container.remove_file("b".to_string());
+        assert_eq!(*events.lock().unwrap(), ["add a", "rename a b", "remove b"]);
+    }
+
+    #[test]
+    fn audit_log_records_mutations() {
+        let mut container = Container::with_timestamp("Audit", 0);
+        container.add_file(File::new("before", vec![]));
+        container.audit_actor = Some("alice".to_string());
+
+        container.add_file(File::new("a", vec![0x01]));
+        container.rename_file("a", "b");
+        container.remove_file("before".to_string());
+        assert!(!container.remove_file(AUDIT_ENTRY.to_string()));
+        assert!(!container.rename_file(AUDIT_ENTRY, "x"));
+        assert!(matches!(
+            container.try_add_file(File::new(AUDIT_ENTRY, vec![])).unwrap_err().downcast_ref::<FofcError>(),
+            Some(FofcError::AuditEntry)
+        ));
+
+        let log = container.audit_log();
+        let actions: Vec<(&str, &AuditAction, &str)> = log.iter().map(|r| (r.actor.as_str(), &r.action, r.name.as_str())).collect();
+        assert_eq!(actions, [
+            ("alice", &AuditAction::Add, "a"),
+            ("alice", &AuditAction::Rename { to: "b".to_string() }, "a"),
+            ("alice", &AuditAction::Remove, "before")
+        ]);
+        assert!(log.iter().all(|r| r.time > 0));
+
+        let reopened = Container::from_bytes(&container.to_bytes().unwrap()).unwrap();
+        assert_eq!(reopened.audit_log(), log);
+    }
+
+    #[test]
+    fn heap_size_counts_names_and_contents() {
+        let mut container = Container::with_timestamp("", 0);
+        let empty = container.heap_size();
+        container.add_file(File::new("name", vec![0x00; 1000]));
+        assert!(container.heap_size() >= empty + 1004 + core::mem::size_of::<File>());
+    }
+
+    #[test]
+    fn add_files_follows_the_duplicate_policy() {
+        let mut container = Container::with_timestamp("Bulk", 0);
+        container.duplicate_policy = DuplicatePolicy::ReplaceExisting;
+        container.add_files((0..1000).map(|i| File::new((i % 500).to_string(), vec![(i / 500) as u8]))).unwrap();
+        assert_eq!(container.files.len(), 500);
+        assert!(container.files.iter().all(|f| f.content[..] == [0x01]));
+
+        container.duplicate_policy = DuplicatePolicy::Reject;
+        let result = container.add_files([File::new("new", vec![]), File::new("7", vec![]), File::new("later", vec![])]);
+        assert!(matches!(result.unwrap_err().downcast_ref::<FofcError>(), Some(FofcError::DuplicateName { name }) if name == "7"));
+        assert!(container.get_file("new".to_string()).is_some());
+        assert!(container.get_file("later".to_string()).is_none());
+    }
+
+    #[test]
+    fn size_accessors() {
+        let mut container = Container::with_timestamp("Sizes", 0);
+        assert!(container.is_empty());
+        container.add_file(File::new("a", vec![0x00; 3]));
+        container.add_file(File::new("b", vec![0x00; 4]));
+        assert_eq!((container.len(), container.is_empty(), container.total_content_size()), (2, false, 7));
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut container = Container::with_timestamp("History", 0);
+        container.duplicate_policy = DuplicatePolicy::ReplaceExisting;
+        container.add_file(File::new("kept", vec![]));
+        assert!(!container.undo());
+
+        container.record_history(true);
+        let start = container.clone();
+        container.add_file(File::new("a", vec![0x01]));
+        container.add_file(File::new("a", vec![0x02]));
+        container.rename_file("a", "b");
+        container.remove_file("kept".to_string());
+        let end = container.clone();
+
+        while container.undo() {}
+        assert_eq!(container, start);
+        assert!(container.get_file("b".to_string()).is_none());
+        while container.redo() {}
+        assert_eq!(container, end);
+        assert_eq!(container.get_file("b".to_string()).unwrap().content[..], [0x02]);
+
+        assert!(container.undo());
+        container.add_file(File::new("c", vec![]));
+        assert!(!container.redo());
+    }
+
+    #[test]
+    fn save_and_open_lock_the_file() {
+        let path = std::env::temp_dir().join(format!("fofc-lock-{}.fofc", std::process::id()));
+        let mut container = Container::with_timestamp("Locked", 0);
+        container.add_file(File::new("a", vec![0x01; 100]));
+        container.save(&path).unwrap();
+
+        container.files[0] = File::new("a", vec![0x02]);
+        container.save(&path).unwrap();
+        assert_eq!(Container::open(&path).unwrap(), container);
+
+        let holder = fs::File::open(&path).unwrap();
+        holder.lock().unwrap();
+        let result = container.save(&path);
+        assert!(matches!(result.unwrap_err().downcast_ref::<FofcError>(), Some(FofcError::WouldBlock)));
+        holder.unlock().unwrap();
+        drop(holder);
+
+        // a save that fails partway leaves the old file and no temporary one behind
+        let saved = container.clone();
+        container.comment = "bad\0comment".to_string();
+        assert!(container.save(&path).is_err());
+        assert_eq!(Container::open(&path).unwrap(), saved);
+        let prefix = format!(".{}.", path.file_name().unwrap().to_string_lossy());
+        let leftovers = fs::read_dir(path.parent().unwrap()).unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with(&prefix))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn save_if_dirty_skips_unchanged_containers() {
+        let path = std::env::temp_dir().join(format!("fofc-dirty-{}.fofc", std::process::id()));
+        let mut container = Container::with_timestamp("Dirty", 0);
+        assert!(!container.is_dirty());
+        assert!(!container.save_if_dirty(&path).unwrap());
+        assert!(!path.exists());
+
+        container.add_file(File::new("a", vec![]));
+        container.rename_file("missing", "b");
+        assert!(container.is_dirty());
+        assert!(container.save_if_dirty(&path).unwrap());
+        assert!(!container.save_if_dirty(&path).unwrap());
+
+        container.add_file(File::new("c", vec![]));
+        container.save(&path).unwrap();
+        assert!(!container.is_dirty());
+        assert!(!container.save_if_dirty(&path).unwrap());
+
+        let mut reopened = Container::open(&path).unwrap();
+        assert!(!reopened.is_dirty());
+        reopened.remove_file("a".to_string());
+        assert!(reopened.is_dirty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn set_comment_rejects_unparseable_comments() {
+        let mut container = Container::with_timestamp("Old", 0);
+        assert!(matches!(container.set_comment("bad\0comment"), Err(FofcError::CommentContainsNul)));
+        assert!(matches!(
+            container.set_comment_with_limit("too long", 4),
+            Err(FofcError::LimitExceeded { limit: Limit::CommentLength, value: 8, max: 4 })
+        ));
+        assert_eq!(container.comment, "Old");
+        assert!(!container.is_dirty());
+
+        container.set_comment_with_limit("New", 4).unwrap();
+        assert_eq!(container.comment, "New");
+        assert!(container.is_dirty());
+
+        container.comment = "x\0y".to_string();
+        let nul = |e: &FofcError| matches!(e, FofcError::CommentContainsNul);
+        assert!(nul(container.to_bytes().unwrap_err().downcast_ref::<FofcError>().unwrap()));
+        assert!(nul(&container.write_to_slice(&mut [0; 64]).unwrap_err()));
+        let mut storage: Vec<u8> = Vec::new();
+        assert!(nul(container.write_to(&mut storage).unwrap_err().downcast_ref::<FofcError>().unwrap()));
+        assert!(storage.is_empty());
+    }
+
+    #[test]
+    fn put_file_replaces_in_place() {
+        let mut container = Container::with_timestamp("Put", 0);
+        container.duplicate_policy = DuplicatePolicy::AllowDuplicates;
+        assert!(container.put_file(File::new("a", vec![0x01])).unwrap().is_none());
+        container.add_file(File::new("b", vec![]));
+
+        let old = container.put_file(File::new("a", vec![0x02])).unwrap();
+        assert_eq!(old, Some(File::new("a", vec![0x01])));
+        let names: Vec<&str> = container.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(container.get_file("a".to_string()).unwrap().content[..], [0x02]);
+    }
+
+    #[test]
+    fn text_accessors() {
+        let text = File::new("a.txt", "héllo".as_bytes().to_vec());
+        assert_eq!(text.as_str(), Ok("héllo"));
+        assert!(matches!(text.as_text_lossy(), Cow::Borrowed("héllo")));
+
+        let binary = File::new("b.bin", vec![b'a', 0xFF]);
+        assert!(binary.as_str().is_err());
+        assert_eq!(binary.as_text_lossy(), "a\u{FFFD}");
+    }
+
+    #[cfg(feature = "chardetng")]
+    #[test]
+    fn decode_text_transcodes_legacy_encodings() {
+        // "Größe und Maße für Grüße" in windows-1252
+        let latin = File::new("de.txt", b"Gr\xF6\xDFe und Ma\xDFe f\xFCr Gr\xFC\xDFe".to_vec());
+        let (text, encoding) = latin.decode_text();
+        assert_eq!(text, "Größe und Maße für Grüße");
+        assert_eq!(encoding, encoding_rs::WINDOWS_1252);
+
+        let utf8 = File::new("a.txt", "plain".as_bytes().to_vec());
+        assert!(matches!(utf8.decode_text(), (Cow::Borrowed("plain"), _)));
+    }
+
+    #[test]
+    fn writers_refuse_more_entries_than_the_count_holds() {
+        let mut container = Container::with_timestamp("Many", 0);
+        container.files = (0..=u16::MAX as usize).map(|i| File::new(i.to_string(), vec![])).collect();
+        let too_many = |e: &FofcError| matches!(e, FofcError::LimitExceeded { limit: Limit::FileCount, value: 65_536, max: 65_535 });
+
+        assert!(too_many(container.to_bytes().unwrap_err().downcast_ref::<FofcError>().unwrap()));
+        let mut buf = vec![0xff; container.serialized_size() as usize];
+        assert!(too_many(&container.write_to_slice(&mut buf).unwrap_err()));
+        assert!(buf.iter().all(|&b| b == 0xff));
+        let mut storage: Vec<u8> = Vec::new();
+        assert!(too_many(container.write_to(&mut storage).unwrap_err().downcast_ref::<FofcError>().unwrap()));
+        assert!(storage.is_empty());
+
+        container.files.pop();
+        assert_eq!(Container::from_bytes(&container.to_bytes().unwrap()).unwrap().files.len(), 65_535);
+    }
 }
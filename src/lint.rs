@@ -0,0 +1,152 @@
+use crate::prelude::*;
+use crate::view::ContainerView;
+use crate::{Container, FofcError, LintOptions, Map, ParseOptions};
+
+/// How much a `Lint` should worry an ingestion service. Ordered, so a policy can be written
+/// as "reject anything at `Warning` or above".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LintKind {
+    /// Starts with a slash or a drive letter, so extracting it naively writes outside the target.
+    AbsolutePath,
+    /// Has a `..` component.
+    PathTraversal,
+    LargeEntry { length: u64 },
+    EmptyName,
+    /// Shares its name with an earlier entry.
+    DuplicateName,
+    /// The stored length runs past the end of the input. Only `lint_bytes` can see this, since
+    /// a parsed container's lengths always match its contents.
+    SizeMismatch { declared: u64, remaining: u64 }
+}
+
+impl LintKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintKind::AbsolutePath | LintKind::PathTraversal | LintKind::SizeMismatch { .. } => Severity::Error,
+            LintKind::EmptyName | LintKind::DuplicateName => Severity::Warning,
+            LintKind::LargeEntry { .. } => Severity::Info
+        }
+    }
+}
+
+/// Something suspicious about one entry, from `Container::lint`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// Position of the entry in `files`, or in the input for `lint_bytes`.
+    pub entry: usize,
+    /// `None` when the entry couldn't be read far enough to get it.
+    pub name: Option<String>,
+    pub kind: LintKind
+}
+
+impl Lint {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+fn is_absolute(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    name.starts_with(['/', '\\']) || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+fn lint_entries<'a>(entries: impl Iterator<Item = (&'a str, u64)>, options: &LintOptions) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut seen: Map<&str, ()> = Map::new();
+
+    for (entry, (name, length)) in entries.enumerate() {
+        let mut flag = |kind: LintKind| lints.push(Lint { entry, name: Some(name.to_string()), kind });
+        if name.is_empty() {
+            flag(LintKind::EmptyName);
+        }
+        if is_absolute(name) {
+            flag(LintKind::AbsolutePath);
+        }
+        if name.split(['/', '\\']).any(|part| part == "..") {
+            flag(LintKind::PathTraversal);
+        }
+        if length > options.large_entry {
+            flag(LintKind::LargeEntry { length });
+        }
+        if seen.insert(name, ()).is_some() {
+            flag(LintKind::DuplicateName);
+        }
+    }
+
+    lints
+}
+
+pub(crate) fn lint(container: &Container, options: &LintOptions) -> Vec<Lint> {
+    lint_entries(container.files.iter().map(|f| (f.name.as_str(), f.content.len() as u64)), options)
+}
+
+pub(crate) fn lint_bytes(bytes: &[u8], options: &LintOptions) -> Result<Vec<Lint>, FofcError> {
+    // Read everything that's there: size problems are what's being looked for, not limits.
+    let parse = ParseOptions { max_file_size: u64::MAX, max_total_size: u64::MAX, ..ParseOptions::default() };
+    let mut warnings = Vec::new();
+    let view = ContainerView::parse_inner(bytes, &parse, Some(&mut warnings))?;
+
+    let mut lints = lint_entries(view.files.iter().map(|f| (f.name.as_ref(), f.content.len() as u64)), options);
+    for warning in warnings {
+        if let (Some(entry), FofcError::TruncatedEntry { declared, remaining }) = (warning.entry, warning.reason) {
+            lints.push(Lint { entry, name: None, kind: LintKind::SizeMismatch { declared, remaining } });
+        }
+    }
+    Ok(lints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::File;
+
+    fn kinds(lints: &[Lint]) -> Vec<(usize, LintKind)> {
+        lints.iter().map(|l| (l.entry, l.kind.clone())).collect()
+    }
+
+    #[test]
+    fn flags_suspicious_entries() {
+        let mut container = Container::with_timestamp("Lint", 0);
+        container.add_file(File::new("/etc/passwd", vec![]));
+        container.add_file(File::new("a/../../b", vec![]));
+        container.add_file(File::new("C:\\x", vec![0x00; 11]));
+        container.add_file(File::new("", vec![]));
+        container.add_file(File::new("fine..txt", vec![]));
+        container.add_file(File::new("fine..txt", vec![]));
+
+        let lints = lint(&container, &LintOptions { large_entry: 10 });
+        assert_eq!(kinds(&lints), [
+            (0, LintKind::AbsolutePath),
+            (1, LintKind::PathTraversal),
+            (2, LintKind::AbsolutePath),
+            (2, LintKind::LargeEntry { length: 11 }),
+            (3, LintKind::EmptyName),
+            (5, LintKind::DuplicateName)
+        ]);
+        assert_eq!(lints[0].name.as_deref(), Some("/etc/passwd"));
+        assert_eq!(lints.iter().filter(|l| l.severity() >= Severity::Warning).count(), 5);
+    }
+
+    #[test]
+    fn lint_bytes_sees_declared_sizes() {
+        let mut container = Container::with_timestamp("Lint", 0);
+        container.add_file(File::new("../a", vec![0x01; 2]));
+        container.add_file(File::new("b", vec![0x01; 8]));
+        let bytes = container.to_bytes().unwrap();
+
+        let lints = lint_bytes(&bytes[..bytes.len() - 3], &LintOptions::default()).unwrap();
+        assert_eq!(kinds(&lints), [
+            (0, LintKind::PathTraversal),
+            (1, LintKind::SizeMismatch { declared: 8, remaining: 5 })
+        ]);
+        assert_eq!(lints[1].name, None);
+        assert!(lint_bytes(&[0x00], &LintOptions::default()).is_err());
+    }
+}
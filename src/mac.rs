@@ -0,0 +1,52 @@
+//! HMAC-SHA256 authentication for symmetric-key deployments, lighter than signatures.
+//!
+//! The MAC is appended to the archive as a trailer. Lenient parsing ignores trailing data, so
+//! a sealed archive still opens in older readers, but strict parsing needs the trailer
+//! stripped first, which `verify_mac` does.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::prelude::*;
+use crate::FofcError;
+
+pub const MAC_LENGTH: usize = 32;
+
+fn mac(key: &[u8]) -> Hmac<Sha256> {
+    Hmac::new_from_slice(key).expect("HMAC takes keys of any length")
+}
+
+/// Appends a MAC of `archive` under `key`.
+pub fn append_mac(archive: &mut Vec<u8>, key: &[u8]) {
+    let tag = mac(key).chain_update(&archive[..]).finalize().into_bytes();
+    archive.extend_from_slice(&tag);
+}
+
+/// Checks the trailer added by `append_mac` and returns the archive without it.
+pub fn verify_mac<'a>(sealed: &'a [u8], key: &[u8]) -> Result<&'a [u8], FofcError> {
+    let split = sealed.len().checked_sub(MAC_LENGTH).ok_or(FofcError::BadSignature)?;
+    let (archive, tag) = sealed.split_at(split);
+    mac(key).chain_update(archive).verify_slice(tag).map_err(|_| FofcError::BadSignature)?;
+    Ok(archive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Container, File, ParseOptions};
+
+    #[test]
+    fn mac_round_trips() {
+        let mut container = Container::with_timestamp("MAC", 0);
+        container.add_file(File::new("a.bin", vec![0x01]));
+        let archive = container.to_bytes().unwrap();
+
+        let mut sealed = archive.clone();
+        append_mac(&mut sealed, b"pipeline key");
+        assert_eq!(verify_mac(&sealed, b"pipeline key").unwrap(), &archive[..]);
+        assert!(matches!(verify_mac(&sealed, b"other key"), Err(FofcError::BadSignature)));
+        assert!(verify_mac(&sealed[..10], b"pipeline key").is_err());
+
+        assert!(Container::from_bytes(&sealed).is_ok());
+        assert!(Container::from_bytes_with_options(&sealed, &ParseOptions::strict()).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! Signed SHA-256 manifests of a container's entries.
+//!
+//! The manifest is text in `sha256sum` format, so extracted files can be checked with
+//! `sha256sum -c` as well as with `Manifest::check`. Store it and its signature as entries of
+//! their own or beside the archive.
+
+use core::error::Error;
+use core::fmt::Write;
+use sha2::{Digest, Sha256};
+use crate::prelude::*;
+use crate::escape::{escape_lines, unescape};
+use crate::signing::{sign_detached, verify_detached, Signer, Verifier};
+use crate::{Container, FofcError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    /// Each entry's name and the SHA-256 of its content, in container order.
+    pub entries: Vec<(String, [u8; 32])>
+}
+
+impl Manifest {
+    pub fn of(container: &Container) -> Manifest {
+        let entries = container.files.iter()
+            .map(|f| (f.name.clone(), Sha256::digest(&f.content).into()))
+            .collect();
+        Manifest { entries }
+    }
+
+    /// Whether `name` is listed with exactly this content.
+    pub fn check(&self, name: &str, content: &[u8]) -> bool {
+        let digest: [u8; 32] = Sha256::digest(content).into();
+        self.entries.iter().any(|(n, d)| n == name && *d == digest)
+    }
+
+    /// Renders `sha256sum` lines. Names with backslashes or line breaks are escaped the way
+    /// `sha256sum` does it, with a leading backslash on the line.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (name, digest) in self.entries.iter() {
+            let escaped = name.contains(['\\', '\n', '\r']);
+            if escaped {
+                text.push('\\');
+            }
+            for b in digest {
+                let _ = write!(text, "{b:02x}");
+            }
+            text.push_str("  ");
+            if escaped {
+                text.push_str(&escape_lines(name));
+            } else {
+                text.push_str(name);
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Manifest, FofcError> {
+        let entries = text.lines().enumerate().filter(|(_, line)| !line.is_empty()).map(|(i, line)| {
+            let error = || FofcError::InvalidManifest { line: i + 1 };
+            let (escaped, line) = match line.strip_prefix('\\') {
+                Some(rest) => (true, rest),
+                None => (false, line)
+            };
+
+            let hex = line.get(..64).ok_or_else(error)?;
+            let mut digest = [0; 32];
+            for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+                let digit = |b: u8| char::from(b).to_digit(16).ok_or_else(error);
+                *byte = (digit(pair[0])? * 16 + digit(pair[1])?) as u8;
+            }
+
+            // `sha256sum` marks binary-mode lines with `*` instead of the second space
+            let name = line.get(64..).and_then(|rest| rest.strip_prefix("  ").or_else(|| rest.strip_prefix(" *")))
+                .ok_or_else(error)?;
+            let name = if escaped { unescape(name) } else { name.to_string() };
+            Ok((name, digest))
+        }).collect::<Result<Vec<_>, FofcError>>()?;
+
+        Ok(Manifest { entries })
+    }
+
+    /// Renders the manifest and signs the text, returning both.
+    pub fn sign<S: Signer + ?Sized>(&self, signer: &S) -> Result<(String, Vec<u8>), Box<dyn Error>> {
+        let text = self.to_text();
+        let signature = sign_detached(text.as_bytes(), signer)?;
+        Ok((text, signature))
+    }
+
+    /// Checks the signature over `text` before parsing it.
+    pub fn from_signed_text<V: Verifier + ?Sized>(text: &str, signature: &[u8], verifier: &V) -> Result<Manifest, Box<dyn Error>> {
+        verify_detached(text.as_bytes(), signature, verifier)?;
+        Ok(Manifest::from_text(text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::SigningKey;
+    use crate::File;
+
+    #[test]
+    fn signed_manifest_round_trips() {
+        let mut container = Container::with_timestamp("Manifest", 0);
+        container.add_file(File::new("a.txt", "hello\n"));
+        container.add_file(File::new("odd\\name\n", vec![0x01]));
+
+        let manifest = Manifest::of(&container);
+        let key = SigningKey::from_bytes(&[9; 32]);
+        let (text, signature) = manifest.sign(&key).unwrap();
+        assert!(text.starts_with("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  a.txt\n"));
+        assert!(text.ends_with("  odd\\\\name\\n\n"));
+
+        let parsed = Manifest::from_signed_text(&text, &signature, &key.verifying_key()).unwrap();
+        assert_eq!(parsed, manifest);
+        assert!(parsed.check("a.txt", b"hello\n"));
+        assert!(!parsed.check("a.txt", b"hello"));
+
+        let tampered = text.replace("a.txt", "b.txt");
+        assert!(Manifest::from_signed_text(&tampered, &signature, &key.verifying_key()).is_err());
+        assert!(matches!(Manifest::from_text("abc  a.txt\n"), Err(FofcError::InvalidManifest { line: 1 })));
+    }
+}
@@ -0,0 +1,371 @@
+use alloc::sync::Arc;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use crate::{File, FofcError};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Accept anything that can be decoded, replacing invalid UTF-8 in names.
+    #[default]
+    Lenient,
+    /// Reject trailing data after the last entry, empty names and (unless overridden by
+    /// `ParseOptions::name_decoding`) names that aren't UTF-8.
+    Strict
+}
+
+/// How entry names that aren't valid UTF-8 are handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameDecoding {
+    /// Replace invalid sequences with U+FFFD.
+    Lossy,
+    /// Fail with `FofcError::NameEncoding`.
+    Strict
+}
+
+/// What to do with an entry whose name is already taken.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Fail with `FofcError::DuplicateName`.
+    Reject,
+    /// Overwrite the existing entry in place.
+    ReplaceExisting,
+    /// Keep both entries; lookups by name return the first.
+    #[default]
+    AllowDuplicates
+}
+
+/// How `Container::merge` settles an entry both containers have with different content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Replace our entry with theirs.
+    TheirsWins,
+    /// Keep our entry and drop theirs.
+    OursWins,
+    /// Fail with `FofcError::DuplicateName` before changing anything.
+    ErrorOnConflict,
+    /// Keep both, adding theirs under a free name like `a-1.txt`.
+    KeepBoth
+}
+
+/// A flag shared between a long-running operation and whoever may want to stop it.
+///
+/// Clones share the same flag. Operations check it between entries and fail with
+/// `FofcError::Cancelled` once it has been set.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How far along a long-running operation is, passed to a `ProgressHook` after each entry.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress<'a> {
+    /// Position of the entry just handled.
+    pub entry: usize,
+    pub entry_count: usize,
+    pub entry_name: &'a str,
+    pub bytes_processed: u64,
+    pub total_bytes: u64
+}
+
+/// A callback that receives `Progress` updates.
+#[derive(Clone)]
+pub struct ProgressHook(Arc<dyn Fn(&Progress<'_>) + Send + Sync>);
+
+impl ProgressHook {
+    pub fn new(callback: impl Fn(&Progress<'_>) + Send + Sync + 'static) -> ProgressHook {
+        ProgressHook(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHook")
+    }
+}
+
+/// Fails with `FofcError::Cancelled` if `cancel` has been triggered.
+fn check_cancelled(cancel: Option<&CancellationToken>) -> Result<(), FofcError> {
+    match cancel {
+        Some(token) if token.is_cancelled() => Err(FofcError::Cancelled),
+        _ => Ok(())
+    }
+}
+
+fn report_progress(hook: Option<&ProgressHook>, progress: &Progress<'_>) {
+    if let Some(hook) = hook {
+        (hook.0)(progress)
+    }
+}
+
+/// Controls how untrusted input is parsed.
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    /// Overrides how names are decoded. `None` follows `mode`: strict for
+    /// `ParseMode::Strict`, lossy otherwise.
+    pub name_decoding: Option<NameDecoding>,
+    /// Largest content length accepted for a single entry.
+    pub max_file_size: u64,
+    /// Largest combined content length accepted across all entries.
+    pub max_total_size: u64,
+    /// Largest number of entries accepted.
+    pub max_file_count: usize,
+    /// Longest entry name accepted, in bytes.
+    pub max_name_length: usize,
+    /// Applied to entries as they're read. In recovering parses, rejected duplicates are skipped.
+    pub duplicate_policy: DuplicatePolicy,
+    pub cancel: Option<CancellationToken>,
+    pub on_progress: Option<ProgressHook>
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            mode: ParseMode::Lenient,
+            name_decoding: None,
+            max_file_size: 4 << 30,
+            max_total_size: 16 << 30,
+            max_file_count: u16::MAX as usize,
+            max_name_length: usize::MAX,
+            duplicate_policy: DuplicatePolicy::AllowDuplicates,
+            cancel: None,
+            on_progress: None
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Default limits with `ParseMode::Strict`.
+    pub fn strict() -> ParseOptions {
+        ParseOptions {
+            mode: ParseMode::Strict,
+            ..ParseOptions::default()
+        }
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<(), FofcError> {
+        check_cancelled(self.cancel.as_ref())
+    }
+
+    pub(crate) fn report_progress(&self, progress: &Progress<'_>) {
+        report_progress(self.on_progress.as_ref(), progress)
+    }
+
+    pub fn name_decoding(&self) -> NameDecoding {
+        match (self.name_decoding, self.mode) {
+            (Some(decoding), _) => decoding,
+            (None, ParseMode::Strict) => NameDecoding::Strict,
+            (None, ParseMode::Lenient) => NameDecoding::Lossy
+        }
+    }
+}
+
+/// Order a container's entries are iterated and written in. Sorting is stable, so entries
+/// that compare equal keep their order in `Container::files`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EntryOrder {
+    /// The order of `Container::files`.
+    #[default]
+    Insertion,
+    /// By name bytes.
+    Name,
+    /// By content length, smallest first.
+    Size
+}
+
+impl EntryOrder {
+    pub(crate) fn sort(self, files: &mut [&File]) {
+        match self {
+            EntryOrder::Insertion => {}
+            EntryOrder::Name => files.sort_by(|a, b| a.name.cmp(&b.name)),
+            EntryOrder::Size => files.sort_by_key(|f| f.content.len())
+        }
+    }
+}
+
+/// Thresholds for `Container::lint`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LintOptions {
+    /// Entries bigger than this are flagged with `LintKind::LargeEntry`.
+    pub large_entry: u64
+}
+
+impl Default for LintOptions {
+    fn default() -> LintOptions {
+        LintOptions { large_entry: 256 << 20 }
+    }
+}
+
+/// Controls how a container is serialized.
+#[derive(Clone, Debug, Default)]
+pub struct WriteOptions {
+    /// Overrides `Container::order`.
+    pub order: Option<EntryOrder>,
+    /// Written in place of the container's own timestamp.
+    pub timestamp: Option<u64>,
+    /// Applied to same-named entries as they're written: `Reject` fails, `ReplaceExisting`
+    /// writes only the last entry of each name, at the first one's position.
+    pub duplicate_policy: DuplicatePolicy,
+    pub cancel: Option<CancellationToken>,
+    pub on_progress: Option<ProgressHook>
+}
+
+impl WriteOptions {
+    /// Sorted entries and a fixed timestamp, so the same entries always produce the same
+    /// bytes. The timestamp comes from `SOURCE_DATE_EPOCH` when it's set, as build systems
+    /// expect, and is zero otherwise.
+    pub fn deterministic() -> WriteOptions {
+        WriteOptions {
+            order: Some(EntryOrder::Name),
+            timestamp: Some(source_date_epoch().unwrap_or(0)),
+            ..WriteOptions::default()
+        }
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<(), FofcError> {
+        check_cancelled(self.cancel.as_ref())
+    }
+
+    pub(crate) fn report_progress(&self, progress: &Progress<'_>) {
+        report_progress(self.on_progress.as_ref(), progress)
+    }
+}
+
+/// Ready-made `WriteOptions` for common jobs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// `WriteOptions::deterministic`, with duplicate names rejected so the output can't be
+    /// ambiguous either.
+    Reproducible,
+    /// Writes the container as it is, doing no extra work.
+    FastDev,
+    /// Sorted by name with duplicates rejected, keeping the container's real timestamp.
+    ArchivalMax
+}
+
+impl Profile {
+    pub fn write_options(self) -> WriteOptions {
+        match self {
+            Profile::Reproducible => WriteOptions {
+                duplicate_policy: DuplicatePolicy::Reject,
+                ..WriteOptions::deterministic()
+            },
+            Profile::FastDev => WriteOptions::default(),
+            Profile::ArchivalMax => WriteOptions {
+                order: Some(EntryOrder::Name),
+                timestamp: None,
+                duplicate_policy: DuplicatePolicy::Reject,
+                ..WriteOptions::default()
+            }
+        }
+    }
+}
+
+/// The `SOURCE_DATE_EPOCH` environment variable, if it holds a valid Unix timestamp.
+#[cfg(feature = "std")]
+pub fn source_date_epoch() -> Option<u64> {
+    parse_source_date_epoch(std::env::var("SOURCE_DATE_EPOCH").ok().as_deref())
+}
+
+/// Without `std` there's no environment to read.
+#[cfg(not(feature = "std"))]
+pub fn source_date_epoch() -> Option<u64> {
+    None
+}
+
+#[cfg(feature = "std")]
+fn parse_source_date_epoch(value: Option<&str>) -> Option<u64> {
+    value?.trim().parse().ok()
+}
+
+/// Parse settings for input from untrusted sources, such as user uploads.
+///
+/// Strict mode, strict name decoding and rejection of duplicate names are always on;
+/// the limits start out conservative and can only be lowered.
+#[cfg(feature = "hardened")]
+#[derive(Clone, Debug)]
+pub struct HardenedOptions {
+    options: ParseOptions
+}
+
+#[cfg(feature = "hardened")]
+impl Default for HardenedOptions {
+    fn default() -> HardenedOptions {
+        HardenedOptions {
+            options: ParseOptions {
+                mode: ParseMode::Strict,
+                name_decoding: Some(NameDecoding::Strict),
+                max_file_size: 64 << 20,
+                max_total_size: 256 << 20,
+                max_file_count: 4096,
+                max_name_length: 1024,
+                duplicate_policy: DuplicatePolicy::Reject,
+                cancel: None,
+                on_progress: None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "hardened")]
+impl HardenedOptions {
+    pub fn max_file_size(mut self, max: u64) -> HardenedOptions {
+        self.options.max_file_size = self.options.max_file_size.min(max);
+        self
+    }
+
+    pub fn max_total_size(mut self, max: u64) -> HardenedOptions {
+        self.options.max_total_size = self.options.max_total_size.min(max);
+        self
+    }
+
+    /// Also bounds how many entries the parser will iterate over.
+    pub fn max_file_count(mut self, max: usize) -> HardenedOptions {
+        self.options.max_file_count = self.options.max_file_count.min(max);
+        self
+    }
+
+    pub fn max_name_length(mut self, max: usize) -> HardenedOptions {
+        self.options.max_name_length = self.options.max_name_length.min(max);
+        self
+    }
+
+    pub fn cancel(mut self, token: CancellationToken) -> HardenedOptions {
+        self.options.cancel = Some(token);
+        self
+    }
+
+    pub fn on_progress(mut self, hook: ProgressHook) -> HardenedOptions {
+        self.options.on_progress = Some(hook);
+        self
+    }
+
+    pub fn as_parse_options(&self) -> &ParseOptions {
+        &self.options
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_date_epoch_parses_timestamps() {
+        assert_eq!(parse_source_date_epoch(Some("1700000000")), Some(1_700_000_000));
+        assert_eq!(parse_source_date_epoch(Some("1700000000\n")), Some(1_700_000_000));
+        assert_eq!(parse_source_date_epoch(Some("yesterday")), None);
+        assert_eq!(parse_source_date_epoch(None), None);
+    }
+}
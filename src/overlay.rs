@@ -0,0 +1,93 @@
+use crate::prelude::*;
+use crate::{Container, File, Map};
+
+/// A stack of containers read as one, where later layers shadow earlier ones.
+///
+/// Lookups check the top layer first. Listings keep the order each name first appeared in,
+/// going up from the base, but show the top-most layer's version of it.
+#[derive(Clone, Debug, Default)]
+pub struct OverlayContainer {
+    layers: Vec<Container>
+}
+
+impl OverlayContainer {
+    /// `layers` go from the base up.
+    pub fn new(layers: impl IntoIterator<Item = Container>) -> OverlayContainer {
+        OverlayContainer { layers: layers.into_iter().collect() }
+    }
+
+    pub fn push_layer(&mut self, layer: Container) {
+        self.layers.push(layer);
+    }
+
+    pub fn layers(&self) -> &[Container] {
+        &self.layers
+    }
+
+    /// The entry called `name` in the top-most layer that has one.
+    pub fn get_file(&self, name: &str) -> Option<&File> {
+        self.layers.iter().rev().find_map(|layer| layer.position(name).map(|i| &layer.files[i]))
+    }
+
+    /// Every visible entry, one per name.
+    pub fn files(&self) -> Vec<&File> {
+        let mut files: Vec<&File> = Vec::new();
+        let mut positions: Map<&str, (usize, usize)> = Map::new();
+
+        for (layer, container) in self.layers.iter().enumerate() {
+            for f in container.files.iter() {
+                match positions.get(f.name.as_str()) {
+                    // only a later layer shadows; within a layer the first entry wins, as in `get_file`
+                    Some(&(i, seen_in)) if seen_in < layer => {
+                        files[i] = f;
+                        positions.insert(&f.name, (i, layer));
+                    }
+                    Some(_) => {}
+                    None => {
+                        positions.insert(&f.name, (files.len(), layer));
+                        files.push(f);
+                    }
+                }
+            }
+        }
+
+        files
+    }
+
+    /// Merges the layers into one container, taking the comment and timestamp from the top layer.
+    pub fn flatten(&self) -> Container {
+        let files = self.files().into_iter().cloned().collect();
+        match self.layers.last() {
+            Some(top) => Container::from_parts(top.comment.clone(), top.x, files),
+            None => Container::from_parts(String::new(), 0, files)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_layers_shadow_earlier_ones() {
+        let mut base = Container::with_timestamp("base", 1);
+        base.add_file(File::new("a.txt", vec![0x01]));
+        base.add_file(File::new("b.txt", vec![0x02]));
+
+        let mut dlc = Container::with_timestamp("dlc", 2);
+        dlc.add_file(File::new("c.txt", vec![0x03]));
+        dlc.add_file(File::new("a.txt", vec![0x04]));
+
+        let overlay = OverlayContainer::new([base, dlc]);
+        assert_eq!(overlay.get_file("a.txt").unwrap().content, vec![0x04]);
+        assert_eq!(overlay.get_file("b.txt").unwrap().content, vec![0x02]);
+        assert!(overlay.get_file("d.txt").is_none());
+
+        let names: Vec<&str> = overlay.files().iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+
+        let flat = overlay.flatten();
+        assert_eq!(flat.comment, "dlc");
+        assert_eq!(flat.get_file("a.txt".to_string()).unwrap().content, vec![0x04]);
+    }
+}
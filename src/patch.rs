@@ -0,0 +1,128 @@
+//! Entry-level patches between two versions of a container.
+//!
+//! A patch lists the new container's entries in order. Entries whose content already exists
+//! somewhere in the old container are recorded as a copy of that entry, so unchanged and
+//! renamed files cost only their name; everything else is carried in full.
+
+use core::error::Error;
+use crate::prelude::*;
+use crate::{Container, File, FofcError, Map};
+
+const COPY: u8 = 0;
+const LITERAL: u8 = 1;
+
+#[derive(Clone, Debug)]
+pub enum PatchEntry {
+    /// The content of the old container's entry at `index`, under `name`.
+    Copy { name: String, index: usize },
+    /// An entry that isn't in the old container.
+    Literal(File)
+}
+
+#[derive(Clone, Debug)]
+pub struct Patch {
+    pub comment: String,
+    pub x: u64,
+    pub entries: Vec<PatchEntry>
+}
+
+/// Describes how to turn `old` into `new`.
+pub fn create_patch(old: &Container, new: &Container) -> Patch {
+    let mut by_content: Map<&[u8], usize> = Map::new();
+    for (i, f) in old.files.iter().enumerate() {
+        by_content.entry(&f.content[..]).or_insert(i);
+    }
+
+    let entries = new.files.iter().map(|f| {
+        // prefer the same-named entry so the patch reads naturally when little changed
+        let same_name = old.position(&f.name).filter(|&i| old.files[i].content == f.content);
+        match same_name.or_else(|| by_content.get(&f.content[..]).copied()) {
+            Some(index) => PatchEntry::Copy { name: f.name.clone(), index },
+            None => PatchEntry::Literal(f.clone())
+        }
+    }).collect();
+
+    Patch { comment: new.comment.clone(), x: new.x, entries }
+}
+
+/// Rebuilds the new container from `old` and a patch made against it. Fails with
+/// `FofcError::PatchBase` if the patch copies an entry `old` doesn't have, which means it was
+/// made against a different container.
+pub fn apply_patch(old: &Container, patch: &Patch) -> Result<Container, FofcError> {
+    let files = patch.entries.iter().map(|entry| match entry {
+        PatchEntry::Copy { name, index } => old.files.get(*index)
+            .map(|f| File::new(name.clone(), f.content.clone()))
+            .ok_or(FofcError::PatchBase { index: *index, entries: old.files.len() }),
+        PatchEntry::Literal(f) => Ok(f.clone())
+    }).collect::<Result<Vec<File>, FofcError>>()?;
+
+    Ok(Container::from_parts(patch.comment.clone(), patch.x, files))
+}
+
+impl Patch {
+    /// Encodes the patch as a container whose entries start with a tag byte: a copy is followed
+    /// by the source index as a little-endian u64, a literal by its content.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let files = self.entries.iter().map(|entry| match entry {
+            PatchEntry::Copy { name, index } => {
+                let mut content = vec![COPY];
+                content.extend_from_slice(&(*index as u64).to_le_bytes());
+                File::new(name.clone(), content)
+            }
+            PatchEntry::Literal(f) => {
+                let mut content = Vec::with_capacity(f.content.len() + 1);
+                content.push(LITERAL);
+                content.extend_from_slice(&f.content);
+                File::new(f.name.clone(), content)
+            }
+        }).collect();
+
+        Container::from_parts(self.comment.clone(), self.x, files).to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Patch, Box<dyn Error>> {
+        let container = Container::from_bytes(bytes)?;
+        let entries = container.files.into_iter().enumerate().map(|(i, f)| match f.content.split_first() {
+            Some((&COPY, index)) => {
+                let index: [u8; 8] = index.try_into().map_err(|_| FofcError::InvalidPatch { entry: i })?;
+                let index = usize::try_from(u64::from_le_bytes(index)).map_err(|_| FofcError::InvalidPatch { entry: i })?;
+                Ok(PatchEntry::Copy { name: f.name, index })
+            }
+            Some((&LITERAL, content)) => Ok(PatchEntry::Literal(File::new(f.name, content.to_vec()))),
+            _ => Err(FofcError::InvalidPatch { entry: i })
+        }).collect::<Result<Vec<PatchEntry>, FofcError>>()?;
+
+        Ok(Patch { comment: container.comment, x: container.x, entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch_round_trips() {
+        let mut old = Container::with_timestamp("v1", 1);
+        old.add_file(File::new("keep.bin", vec![0x01; 100]));
+        old.add_file(File::new("old-name.bin", vec![0x02; 100]));
+        old.add_file(File::new("gone.bin", vec![0x03]));
+
+        let mut new = Container::with_timestamp("v2", 2);
+        new.add_file(File::new("added.bin", vec![0x04]));
+        new.add_file(File::new("keep.bin", vec![0x01; 100]));
+        new.add_file(File::new("new-name.bin", vec![0x02; 100]));
+
+        let patch = create_patch(&old, &new);
+        assert!(matches!(&patch.entries[1], PatchEntry::Copy { index: 0, .. }));
+        assert!(matches!(&patch.entries[2], PatchEntry::Copy { index: 1, .. }));
+
+        let bytes = patch.to_bytes().unwrap();
+        assert!(bytes.len() < new.to_bytes().unwrap().len());
+
+        let rebuilt = apply_patch(&old, &Patch::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(rebuilt.to_bytes().unwrap(), new.to_bytes().unwrap());
+
+        let empty = Container::with_timestamp("v0", 0);
+        assert!(matches!(apply_patch(&empty, &patch), Err(FofcError::PatchBase { index: 0, entries: 0 })));
+    }
+}
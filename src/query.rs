@@ -0,0 +1,74 @@
+use crate::glob::Glob;
+use crate::prelude::*;
+use crate::File;
+
+/// A reusable selection of entries, built up from name and size conditions.
+///
+/// An entry is selected when it matches at least one `name` pattern (or none were given),
+/// no `exclude` pattern, and every size bound. Patterns use the same glob syntax as
+/// `Container::find`.
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    min_size: Option<u64>,
+    max_size: Option<u64>
+}
+
+impl Query {
+    pub fn new() -> Query {
+        Query::default()
+    }
+
+    pub fn name(mut self, pattern: &str) -> Query {
+        self.include.push(Glob::new(pattern));
+        self
+    }
+
+    pub fn exclude(mut self, pattern: &str) -> Query {
+        self.exclude.push(Glob::new(pattern));
+        self
+    }
+
+    /// Only entries with at least `bytes` of content.
+    pub fn min_size(mut self, bytes: u64) -> Query {
+        self.min_size = Some(bytes);
+        self
+    }
+
+    /// Only entries with at most `bytes` of content.
+    pub fn max_size(mut self, bytes: u64) -> Query {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    pub fn matches(&self, file: &File) -> bool {
+        let size = file.content.len() as u64;
+        (self.include.is_empty() || self.include.iter().any(|g| g.matches(&file.name)))
+            && !self.exclude.iter().any(|g| g.matches(&file.name))
+            && self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Container;
+
+    #[test]
+    fn conditions_combine() {
+        let mut container = Container::with_timestamp("Query", 0);
+        container.add_file(File::new("img/big.png", vec![0x01; 100]));
+        container.add_file(File::new("img/small.png", vec![0x01; 2]));
+        container.add_file(File::new("img/skip.png", vec![0x01; 100]));
+        container.add_file(File::new("doc.txt", vec![0x01; 100]));
+
+        let query = Query::new().name("img/*").exclude("*/skip.*").min_size(10);
+        let names: Vec<&str> = container.query(&query).map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["img/big.png"]);
+
+        assert_eq!(container.query(&Query::new().max_size(2)).count(), 1);
+        assert_eq!(container.query(&Query::new()).count(), 4);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::prelude::*;
+use crate::{Container, Map};
+
+/// Where a word was found: the entry's name and position in `Container::files`, and the byte
+/// offset of the word in its content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchHit<'a> {
+    pub name: &'a str,
+    pub entry: usize,
+    pub offset: usize
+}
+
+/// An inverted index from words to where they appear in a container's text entries.
+///
+/// Only entries whose content is valid UTF-8 are indexed. A word is a run of alphanumeric
+/// characters and underscores, matched regardless of case. The index lives in memory; it is
+/// not stored in the container.
+#[derive(Clone, Debug)]
+pub struct SearchIndex<'a> {
+    container: &'a Container,
+    words: Map<String, Vec<(usize, usize)>>
+}
+
+impl<'a> SearchIndex<'a> {
+    pub fn build(container: &'a Container) -> SearchIndex<'a> {
+        let mut words: Map<String, Vec<(usize, usize)>> = Map::new();
+        for (entry, f) in container.files.iter().enumerate() {
+            let Ok(text) = core::str::from_utf8(&f.content) else {
+                continue;
+            };
+            for (offset, word) in split_words(text) {
+                words.entry(word.to_lowercase()).or_default().push((entry, offset));
+            }
+        }
+
+        SearchIndex { container, words }
+    }
+
+    /// Every occurrence of `word`, in entry then offset order.
+    pub fn search(&self, word: &str) -> Vec<SearchHit<'a>> {
+        let container = self.container;
+        self.words.get(&word.to_lowercase()).into_iter().flatten()
+            .map(|&(entry, offset)| SearchHit { name: &container.files[entry].name, entry, offset })
+            .collect()
+    }
+}
+
+/// A line containing the pattern, from `Container::grep`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GrepMatch<'a> {
+    pub name: &'a str,
+    /// Starting at 1.
+    pub line_number: usize,
+    pub line: &'a str
+}
+
+pub(crate) fn grep<'a>(container: &'a Container, pattern: &'a str) -> impl Iterator<Item = GrepMatch<'a>> + 'a {
+    container.files.iter()
+        .filter_map(|f| core::str::from_utf8(&f.content).ok().map(|text| (f, text)))
+        .flat_map(move |(f, text)| text.lines().enumerate()
+            .filter(move |(_, line)| line.contains(pattern))
+            .map(move |(i, line)| GrepMatch { name: &f.name, line_number: i + 1, line }))
+}
+
+fn split_words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut start = None;
+    text.char_indices().chain(core::iter::once((text.len(), ' '))).filter_map(move |(i, c)| {
+        match (start, is_word(c)) {
+            (None, true) => {
+                start = Some(i);
+                None
+            }
+            (Some(s), false) => {
+                start = None;
+                Some((s, &text[s..i]))
+            }
+            _ => None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::File;
+
+    #[test]
+    fn search_finds_words_in_text_entries() {
+        let mut container = Container::with_timestamp("Docs", 0);
+        container.add_file(File::new("a.md", "TODO: write\nmore todo_items, todo."));
+        container.add_file(File::new("b.bin", vec![b'T', b'O', b'D', b'O', 0xff]));
+        container.add_file(File::new("c.md", "nothing to do"));
+
+        let index = SearchIndex::build(&container);
+        let hits = index.search("todo");
+        assert_eq!(hits, [
+            SearchHit { name: "a.md", entry: 0, offset: 0 },
+            SearchHit { name: "a.md", entry: 0, offset: 29 }
+        ]);
+        assert_eq!(index.search("TODO_ITEMS").len(), 1);
+        assert!(index.search("missing").is_empty());
+    }
+
+    #[test]
+    fn grep_reports_matching_lines() {
+        let mut container = Container::with_timestamp("Docs", 0);
+        container.add_file(File::new("a.rs", "fn main() {\n    // TODO: args\n}\n"));
+        container.add_file(File::new("b.bin", vec![b'T', b'O', b'D', b'O', 0xff]));
+        container.add_file(File::new("c.rs", "// TODO"));
+
+        let matches: Vec<GrepMatch> = grep(&container, "TODO").collect();
+        assert_eq!(matches, [
+            GrepMatch { name: "a.rs", line_number: 2, line: "    // TODO: args" },
+            GrepMatch { name: "c.rs", line_number: 1, line: "// TODO" }
+        ]);
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::{Arc, PoisonError, RwLock};
+use crate::{Container, File};
+
+/// A container many threads can read at once, for servers that keep one bundle loaded across
+/// request handlers.
+///
+/// Readers take a snapshot, which only holds the lock long enough to clone an `Arc`, and then
+/// query it without any locking. Writers edit a copy and swap it in, so snapshots already taken
+/// never change under their holders.
+#[derive(Clone, Debug)]
+pub struct SharedContainer {
+    current: Arc<RwLock<Arc<Container>>>
+}
+
+impl SharedContainer {
+    pub fn new(container: Container) -> SharedContainer {
+        SharedContainer { current: Arc::new(RwLock::new(Arc::new(container))) }
+    }
+
+    /// The container as of now. Later updates don't affect it.
+    pub fn snapshot(&self) -> Arc<Container> {
+        self.current.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// The first entry called `name` in the current snapshot.
+    pub fn get_file(&self, name: &str) -> Option<File> {
+        let snapshot = self.snapshot();
+        snapshot.position(name).map(|i| snapshot.files[i].clone())
+    }
+
+    /// Applies `edit` to a copy of the current container and publishes the result. Updates are
+    /// serialized against each other; readers carry on with the previous snapshot meanwhile.
+    pub fn update<T>(&self, edit: impl FnOnce(&mut Container) -> T) -> T {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        let mut next = Container::clone(&current);
+        let value = edit(&mut next);
+        *current = Arc::new(next);
+        value
+    }
+}
+
+impl From<Container> for SharedContainer {
+    fn from(container: Container) -> SharedContainer {
+        SharedContainer::new(container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn readers_keep_their_snapshot() {
+        let shared = SharedContainer::new(Container::with_timestamp("Shared", 0));
+        let before = shared.snapshot();
+
+        let handles: Vec<_> = (0..4).map(|i| {
+            let shared = shared.clone();
+            thread::spawn(move || shared.update(|c| c.add_file(File::new(i.to_string(), vec![i as u8]))))
+        }).collect();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        assert!(before.is_empty());
+        assert_eq!(shared.snapshot().len(), 4);
+        assert_eq!(shared.get_file("2").unwrap().content[..], [0x02]);
+        assert!(shared.get_file("9").is_none());
+    }
+}
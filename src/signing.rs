@@ -0,0 +1,88 @@
+//! Detached signatures over serialized containers.
+//!
+//! The signature covers the exact bytes of the archive, so it is kept beside it (for example
+//! as `archive.fofc.sig`) and the archive itself stays untouched. Signing goes through the
+//! `Signer` and `Verifier` traits, so keys can live in an HSM or a KMS; Ed25519 keys implement
+//! them directly and produce 64 raw bytes.
+
+use core::error::Error;
+pub use ed25519_dalek::{SigningKey, VerifyingKey, SIGNATURE_LENGTH};
+use ed25519_dalek::Signature;
+use crate::prelude::*;
+use crate::FofcError;
+
+/// Something that can sign bytes, such as a key in memory or a handle to external hardware.
+pub trait Signer {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn Error>>;
+}
+
+/// Checks signatures made by the matching `Signer`. Fails with `FofcError::BadSignature` when
+/// a signature doesn't match.
+pub trait Verifier {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), Box<dyn Error>>;
+}
+
+impl Signer for SigningKey {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(ed25519_dalek::Signer::sign(self, message).to_bytes().to_vec())
+    }
+}
+
+impl Verifier for VerifyingKey {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), Box<dyn Error>> {
+        let signature = Signature::from_slice(signature).map_err(|_| FofcError::BadSignature)?;
+        ed25519_dalek::Verifier::verify(self, message, &signature).map_err(|_| FofcError::BadSignature)?;
+        Ok(())
+    }
+}
+
+/// Signs a serialized container, as produced by `Container::to_bytes`.
+pub fn sign_detached<S: Signer + ?Sized>(archive: &[u8], signer: &S) -> Result<Vec<u8>, Box<dyn Error>> {
+    signer.sign(archive)
+}
+
+/// Checks a detached signature made by `sign_detached`.
+pub fn verify_detached<V: Verifier + ?Sized>(archive: &[u8], signature: &[u8], verifier: &V) -> Result<(), Box<dyn Error>> {
+    verifier.verify(archive, signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Container, File};
+
+    #[test]
+    fn detached_signature_round_trips() {
+        let mut container = Container::with_timestamp("Signed", 0);
+        container.add_file(File::new("a.bin", vec![0x01]));
+        let archive = container.to_bytes().unwrap();
+
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let signature = sign_detached(&archive, &key).unwrap();
+        assert_eq!(signature.len(), SIGNATURE_LENGTH);
+        verify_detached(&archive, &signature, &key.verifying_key()).unwrap();
+
+        let mut tampered = archive.clone();
+        *tampered.last_mut().unwrap() ^= 0xff;
+        let e = verify_detached(&tampered, &signature, &key.verifying_key()).unwrap_err();
+        assert!(matches!(e.downcast_ref::<FofcError>(), Some(FofcError::BadSignature)));
+        assert!(verify_detached(&archive, &signature[..10], &key.verifying_key()).is_err());
+    }
+
+    #[test]
+    fn external_signers_plug_in() {
+        // stands in for a signer that hands the message to hardware and returns its answer
+        struct Remote(SigningKey);
+
+        impl Signer for Remote {
+            fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+                self.0.sign(message)
+            }
+        }
+
+        let remote = Remote(SigningKey::from_bytes(&[3; 32]));
+        let signer: &dyn Signer = &remote;
+        let signature = sign_detached(b"archive", signer).unwrap();
+        verify_detached(b"archive", &signature, &remote.0.verifying_key()).unwrap();
+    }
+}
@@ -0,0 +1,55 @@
+use alloc::sync::Arc;
+use core::ops::Deref;
+use crate::Container;
+
+/// A frozen copy of a container, from `Container::snapshot`. Derefs to `Container` for reading
+/// and serializing, clones by bumping a reference count, and is `Send`, so it can be handed to a
+/// background writer while the original keeps being edited.
+#[derive(Clone, Debug)]
+pub struct Snapshot(Arc<Container>);
+
+impl Snapshot {
+    pub(crate) fn new(container: Container) -> Snapshot {
+        Snapshot(Arc::new(container))
+    }
+
+    /// An editable copy of the frozen state.
+    pub fn to_container(&self) -> Container {
+        Container::clone(&self.0)
+    }
+}
+
+impl Deref for Snapshot {
+    type Target = Container;
+
+    fn deref(&self) -> &Container {
+        &self.0
+    }
+}
+
+impl PartialEq for Snapshot {
+    fn eq(&self, other: &Snapshot) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Snapshot {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Container, File};
+
+    #[test]
+    fn stays_frozen() {
+        let mut container = Container::with_timestamp("Snap", 0);
+        container.add_file(File::new("a", vec![0x01]));
+        let snapshot = container.snapshot();
+        let bytes = snapshot.to_bytes().unwrap();
+
+        container.add_file(File::new("b", vec![]));
+        container.remove_file("a".to_string());
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot.clone().to_bytes().unwrap(), bytes);
+        assert_eq!(snapshot.to_container().get_file("a".to_string()).unwrap().content[..], [0x01]);
+    }
+}
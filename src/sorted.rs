@@ -0,0 +1,71 @@
+use core::ops::{Bound, RangeBounds};
+use crate::prelude::*;
+use crate::{Container, File};
+
+/// A container's entries ordered by name, for binary-search lookups and range queries.
+///
+/// Built on demand from a snapshot of the container, so build a new one after editing it.
+/// Entries with the same name keep their order in `files`.
+#[derive(Clone, Debug)]
+pub struct SortedIndex<'a> {
+    container: &'a Container,
+    order: Vec<usize>
+}
+
+impl<'a> SortedIndex<'a> {
+    pub fn build(container: &'a Container) -> SortedIndex<'a> {
+        let mut order: Vec<usize> = (0..container.files.len()).collect();
+        order.sort_by(|&a, &b| container.files[a].name.cmp(&container.files[b].name));
+        SortedIndex { container, order }
+    }
+
+    fn name(&self, i: usize) -> &'a str {
+        &self.container.files[self.order[i]].name
+    }
+
+    /// The first entry called `name`.
+    pub fn get(&self, name: &str) -> Option<&'a File> {
+        let i = self.order.partition_point(|&e| self.container.files[e].name.as_str() < name);
+        (i < self.order.len() && self.name(i) == name).then(|| &self.container.files[self.order[i]])
+    }
+
+    /// Entries whose names fall in `range`, in name order. `"logs/2024-01".."logs/2024-02"`
+    /// selects every name starting with `logs/2024-01`.
+    pub fn range<'r, R: RangeBounds<&'r str>>(&self, range: R) -> impl Iterator<Item = &'a File> + '_ {
+        let start = match range.start_bound() {
+            Bound::Included(from) => self.order.partition_point(|&e| self.container.files[e].name.as_str() < *from),
+            Bound::Excluded(from) => self.order.partition_point(|&e| self.container.files[e].name.as_str() <= *from),
+            Bound::Unbounded => 0
+        };
+        let end = match range.end_bound() {
+            Bound::Included(to) => self.order.partition_point(|&e| self.container.files[e].name.as_str() <= *to),
+            Bound::Excluded(to) => self.order.partition_point(|&e| self.container.files[e].name.as_str() < *to),
+            Bound::Unbounded => self.order.len()
+        };
+
+        let container = self.container;
+        self.order[start..end.max(start)].iter().map(move |&e| &container.files[e])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_queries_follow_name_order() {
+        let mut container = Container::with_timestamp("Logs", 0);
+        for name in ["logs/2024-02-01", "logs/2024-01-15", "logs/2023-12-31", "logs/2024-01-02"] {
+            container.add_file(File::new(name, vec![0x01]));
+        }
+
+        let index = SortedIndex::build(&container);
+        let names: Vec<&str> = index.range("logs/2024-01".."logs/2024-02").map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["logs/2024-01-02", "logs/2024-01-15"]);
+        assert_eq!(index.range(.."logs/2024").count(), 1);
+        assert_eq!(index.range("z".."a").count(), 0);
+
+        assert_eq!(index.get("logs/2024-01-15").unwrap().name, "logs/2024-01-15");
+        assert!(index.get("logs/2024").is_none());
+    }
+}
@@ -0,0 +1,103 @@
+use core::cmp::Reverse;
+use crate::prelude::*;
+use crate::{Container, File, Map};
+
+/// Summary figures for a container's entries, from `Container::stats`.
+#[derive(Clone, Debug)]
+pub struct ContainerStats<'a> {
+    pub entries: usize,
+    /// Content bytes across every entry, not counting headers.
+    pub total_bytes: u64,
+    /// `None` when there are no entries, as is `max_size`.
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Zero when there are no entries.
+    pub mean_size: f64,
+    /// Largest first, with ties in the order they appear in `files`.
+    pub largest: Vec<&'a File>,
+    /// Biggest share of bytes first.
+    pub extensions: Vec<ExtensionStats>
+}
+
+/// Entries sharing an extension, within `ContainerStats`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionStats {
+    /// Lowercased and without the dot. Empty for names without one.
+    pub extension: String,
+    pub entries: usize,
+    pub bytes: u64
+}
+
+/// What comes after the last dot of the last path component. Dotfiles like `.gitignore`
+/// have no extension.
+fn extension(name: &str) -> &str {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    match base.rfind('.') {
+        Some(i) if i > 0 => &base[i + 1..],
+        _ => ""
+    }
+}
+
+pub(crate) fn stats(container: &Container, top: usize) -> ContainerStats<'_> {
+    let sizes = container.files.iter().map(|f| f.content.len() as u64);
+    let total_bytes: u64 = sizes.clone().sum();
+    let entries = container.files.len();
+
+    let mut largest: Vec<&File> = container.files.iter().collect();
+    largest.sort_by_key(|f| Reverse(f.content.len()));
+    largest.truncate(top);
+
+    let mut by_extension: Map<String, (usize, u64)> = Map::new();
+    for f in container.files.iter() {
+        let totals = by_extension.entry(extension(&f.name).to_lowercase()).or_insert((0, 0));
+        totals.0 += 1;
+        totals.1 += f.content.len() as u64;
+    }
+    let mut extensions: Vec<ExtensionStats> = by_extension.into_iter()
+        .map(|(extension, (entries, bytes))| ExtensionStats { extension, entries, bytes })
+        .collect();
+    extensions.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.extension.cmp(&b.extension)));
+
+    ContainerStats {
+        entries,
+        total_bytes,
+        min_size: sizes.clone().min(),
+        max_size: sizes.max(),
+        mean_size: if entries == 0 { 0.0 } else { total_bytes as f64 / entries as f64 },
+        largest,
+        extensions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_entries() {
+        let mut container = Container::with_timestamp("Stats", 0);
+        container.add_file(File::new("img/a.PNG", vec![0x00; 30]));
+        container.add_file(File::new("img/b.png", vec![0x00; 10]));
+        container.add_file(File::new("notes.txt", vec![0x00; 20]));
+        container.add_file(File::new("dir.d/README", vec![]));
+
+        let stats = stats(&container, 2);
+        assert_eq!(stats.entries, 4);
+        assert_eq!(stats.total_bytes, 60);
+        assert_eq!((stats.min_size, stats.max_size), (Some(0), Some(30)));
+        assert_eq!(stats.mean_size, 15.0);
+
+        let largest: Vec<&str> = stats.largest.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(largest, ["img/a.PNG", "notes.txt"]);
+
+        assert_eq!(stats.extensions, [
+            ExtensionStats { extension: "png".to_string(), entries: 2, bytes: 40 },
+            ExtensionStats { extension: "txt".to_string(), entries: 1, bytes: 20 },
+            ExtensionStats { extension: String::new(), entries: 1, bytes: 0 }
+        ]);
+
+        let empty = Container::with_timestamp("Empty", 0);
+        let stats = super::stats(&empty, 5);
+        assert_eq!((stats.min_size, stats.max_size, stats.mean_size), (None, None, 0.0));
+    }
+}
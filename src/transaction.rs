@@ -0,0 +1,104 @@
+use core::error::Error;
+use crate::hooks::Observers;
+use crate::prelude::*;
+use crate::{Container, File};
+
+enum Change {
+    Add(File),
+    Remove(String),
+    Rename(String, String)
+}
+
+/// A batch of edits from `Container::transaction`. Each edit is tried against a copy of the
+/// container straight away, so it fails where it would have, and `container` shows the
+/// result so far. Nothing reaches the real container until the closure returns `Ok`.
+pub struct Transaction {
+    staged: Container,
+    changes: Vec<Change>
+}
+
+impl Transaction {
+    /// The container as it will be if the transaction commits.
+    pub fn container(&self) -> &Container {
+        &self.staged
+    }
+
+    pub fn add(&mut self, file: File) -> Result<(), Box<dyn Error>> {
+        self.staged.try_add_file(file.clone())?;
+        self.changes.push(Change::Add(file));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let removed = self.staged.remove_file(name.to_string());
+        if removed {
+            self.changes.push(Change::Remove(name.to_string()));
+        }
+        removed
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        let renamed = self.staged.rename_file(from, to);
+        if renamed {
+            self.changes.push(Change::Rename(from.to_string(), to.to_string()));
+        }
+        renamed
+    }
+}
+
+pub(crate) fn run<T>(
+    container: &mut Container,
+    edit: impl FnOnce(&mut Transaction) -> Result<T, Box<dyn Error>>
+) -> Result<T, Box<dyn Error>> {
+    let mut staged = container.clone();
+    // observers only hear about changes once they're committed
+    staged.observers = Observers::default();
+    let mut tx = Transaction { staged, changes: Vec::new() };
+    let value = edit(&mut tx)?;
+
+    // every change already succeeded on an identical copy, so replaying them can't fail
+    for change in tx.changes {
+        match change {
+            Change::Add(file) => container.try_add_file(file)?,
+            Change::Remove(name) => {
+                container.remove_file(name);
+            }
+            Change::Rename(from, to) => {
+                container.rename_file(&from, &to);
+            }
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DuplicatePolicy, FofcError};
+
+    #[test]
+    fn commits_all_or_nothing() {
+        let mut container = Container::with_timestamp("Tx", 0);
+        container.duplicate_policy = DuplicatePolicy::Reject;
+        container.add_file(File::new("a", vec![0x01]));
+        let before = container.clone();
+
+        let result = container.transaction(|tx| {
+            tx.add(File::new("b", vec![]))?;
+            assert!(tx.remove("a"));
+            assert!(tx.container().get_file("b".to_string()).is_some());
+            tx.add(File::new("b", vec![]))
+        });
+        assert!(matches!(result.unwrap_err().downcast_ref::<FofcError>(), Some(FofcError::DuplicateName { .. })));
+        assert_eq!(container, before);
+
+        let count = container.transaction(|tx| {
+            tx.add(File::new("b", vec![0x02]))?;
+            tx.rename("a", "c");
+            Ok(tx.container().len())
+        }).unwrap();
+        assert_eq!(count, 2);
+        let names: Vec<&str> = container.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["c", "b"]);
+    }
+}
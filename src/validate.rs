@@ -0,0 +1,100 @@
+use crate::prelude::*;
+use crate::view::ContainerView;
+use crate::{ParseOptions, ParseWarning};
+#[cfg(feature = "std")]
+use core::error::Error;
+#[cfg(feature = "std")]
+use crate::{ContainerStorage, FofcError, LazyContainer};
+
+/// What `Container::validate` found. Every problem is listed rather than just the first, so a
+/// rejection can say everything that's wrong with the input at once.
+#[derive(Debug)]
+pub struct ValidationReport {
+    /// Entries that were read without problems.
+    pub entries: usize,
+    /// Content bytes across those entries.
+    pub content_bytes: u64,
+    pub problems: Vec<ParseWarning>
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+pub(crate) fn validate(bytes: &[u8], options: &ParseOptions) -> ValidationReport {
+    let mut problems = Vec::new();
+    match ContainerView::parse_inner(bytes, options, Some(&mut problems)) {
+        Ok(view) => ValidationReport {
+            entries: view.files.len(),
+            content_bytes: view.files.iter().map(|f| f.content.len() as u64).sum(),
+            problems
+        },
+        Err(e) => {
+            problems.push(ParseWarning::new(e));
+            ValidationReport { entries: 0, content_bytes: 0, problems }
+        }
+    }
+}
+
+/// Storage is walked with `LazyContainer`, which stops at the first problem, so there's at
+/// most one. I/O failures aren't problems with the container and are returned as errors.
+#[cfg(feature = "std")]
+pub(crate) fn validate_storage<S: ContainerStorage>(storage: S, options: &ParseOptions) -> Result<ValidationReport, Box<dyn Error>> {
+    match LazyContainer::open_with_options(storage, options) {
+        Ok(lazy) => Ok(ValidationReport {
+            entries: lazy.entries().len(),
+            content_bytes: lazy.entries().iter().map(|e| e.length).sum(),
+            problems: Vec::new()
+        }),
+        Err(e) => {
+            let e = e.downcast::<FofcError>()?;
+            Ok(ValidationReport { entries: 0, content_bytes: 0, problems: vec![ParseWarning::new(*e)] })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Container, Field, File, FofcError, ParseMode};
+
+    fn sample() -> Vec<u8> {
+        let mut container = Container::with_timestamp("Valid", 0);
+        container.add_file(File::new("a", vec![0x01; 4]));
+        container.add_file(File::new("", vec![0x02; 2]));
+        container.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let bytes = sample();
+        let report = validate(&bytes, &ParseOptions::default());
+        assert!(report.is_valid());
+        assert_eq!((report.entries, report.content_bytes), (2, 6));
+
+        let strict = ParseOptions { mode: ParseMode::Strict, ..ParseOptions::default() };
+        let mut trailing = bytes.clone();
+        trailing.push(0x00);
+        let report = validate(&trailing, &strict);
+        let fields: Vec<Field> = report.problems.iter().map(|p| p.field).collect();
+        assert_eq!(fields, [Field::FileName, Field::TrailingData]);
+        assert_eq!(report.entries, 1);
+
+        let report = validate(&[0x00], &ParseOptions::default());
+        assert!(matches!(report.problems[0].reason, FofcError::BadMagic { found: 0x00 }));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn validates_storage() {
+        let bytes = sample();
+        let report = validate_storage(bytes.as_slice(), &ParseOptions::default()).unwrap();
+        assert!(report.is_valid());
+        assert_eq!((report.entries, report.content_bytes), (2, 6));
+
+        let report = validate_storage(&bytes[..bytes.len() - 1], &ParseOptions::default()).unwrap();
+        assert!(matches!(report.problems[0].reason, FofcError::TruncatedEntry { declared: 2, remaining: 1 }));
+    }
+}
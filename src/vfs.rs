@@ -0,0 +1,102 @@
+//! Platform-independent directory tree over a container. Only the FUSE adapter uses it so
+//! far; it's kept apart from that adapter so a Windows one could build on the same tree.
+//!
+//! Entry names are split on `/` into directories: `docs/a.txt` shows up as `a.txt` inside a
+//! `docs` directory. Entries whose names can't be represented as a path (empty components,
+//! `.`, `..`, or a file and a directory with the same name) are left out, and only the first
+//! of several same-named entries is visible, as with `Container::get_file`.
+
+use std::collections::BTreeMap;
+use crate::Container;
+
+/// Id of the root directory. Ids start at 1 and match FUSE inode numbers.
+pub(crate) const ROOT: u64 = 1;
+
+pub(crate) enum Node {
+    Directory { parent: u64, children: BTreeMap<String, u64> },
+    /// Index into `Container::files`.
+    File { entry: usize }
+}
+
+pub(crate) struct Tree {
+    /// The node with id `n` is at `n - 1`.
+    nodes: Vec<Node>
+}
+
+impl Tree {
+    pub(crate) fn new(container: &Container) -> Tree {
+        let mut tree = Tree { nodes: vec![Node::Directory { parent: ROOT, children: BTreeMap::new() }] };
+        for (entry, f) in container.files.iter().enumerate() {
+            tree.insert(entry, &f.name);
+        }
+        tree
+    }
+
+    fn insert(&mut self, entry: usize, name: &str) {
+        let mut components: Vec<&str> = name.split('/').collect();
+        if components.iter().any(|c| c.is_empty() || *c == "." || *c == "..") {
+            return;
+        }
+
+        let file_name = components.pop().unwrap_or_default();
+        let mut directory = ROOT;
+        for component in components {
+            directory = match self.child(directory, component) {
+                Some(id) if matches!(self.node(id), Some(Node::Directory { .. })) => id,
+                Some(_) => return,
+                None => self.add_child(directory, component, Node::Directory { parent: directory, children: BTreeMap::new() })
+            };
+        }
+
+        if self.child(directory, file_name).is_none() {
+            self.add_child(directory, file_name, Node::File { entry });
+        }
+    }
+
+    fn add_child(&mut self, directory: u64, name: &str, node: Node) -> u64 {
+        self.nodes.push(node);
+        let id = self.nodes.len() as u64;
+        if let Some(Node::Directory { children, .. }) = self.nodes.get_mut(directory as usize - 1) {
+            children.insert(name.to_string(), id);
+        }
+        id
+    }
+
+    pub(crate) fn node(&self, id: u64) -> Option<&Node> {
+        self.nodes.get(usize::try_from(id).ok()?.checked_sub(1)?)
+    }
+
+    pub(crate) fn child(&self, directory: u64, name: &str) -> Option<u64> {
+        match self.node(directory)? {
+            Node::Directory { children, .. } => children.get(name).copied(),
+            Node::File { .. } => None
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::File;
+
+    #[test]
+    fn names_become_directories() {
+        let mut container = Container::with_timestamp("VFS", 0);
+        container.add_file(File::new("docs/a.txt", vec![0x01]));
+        container.add_file(File::new("docs/b.txt", vec![0x02]));
+        container.add_file(File::new("top.bin", vec![0x03]));
+        container.add_file(File::new("docs", vec![0x04]));
+        container.add_file(File::new("bad//name", vec![0x05]));
+        let tree = Tree::new(&container);
+
+        let docs = tree.child(ROOT, "docs").unwrap();
+        assert!(matches!(tree.node(docs), Some(Node::Directory { parent: ROOT, .. })));
+        let a = tree.child(docs, "a.txt").unwrap();
+        assert!(matches!(tree.node(a), Some(Node::File { entry: 0 })));
+        assert!(tree.child(docs, "b.txt").is_some());
+        assert!(matches!(tree.node(tree.child(ROOT, "top.bin").unwrap()), Some(Node::File { entry: 2 })));
+        assert!(tree.child(ROOT, "bad").is_none());
+        assert!(tree.node(0).is_none());
+    }
+}
@@ -0,0 +1,555 @@
+use core::error::Error;
+use core::fmt;
+use crate::prelude::*;
+use crate::{check_header, encode_entry, encode_header, Container, DuplicatePolicy, Field, File, FofcError, Limit, Map, NameDecoding, ParseMode, ParseOptions, Progress};
+use crate::{MAGIC_NUMBER, Y_DIFFERENCE, Z_DIFFERENCE};
+#[cfg(feature = "hardened")]
+use crate::HardenedOptions;
+
+/// A parsed container whose entries borrow from the input buffer instead of copying it.
+///
+/// Names are only copied when they aren't valid UTF-8 and content is never copied,
+/// so inspecting or selectively re-serializing a large buffer stays cheap.
+#[derive(Debug)]
+pub struct ContainerView<'a> {
+    pub comment: Cow<'a, str>,
+    pub x: u64,
+    pub y: u64,
+    pub z: u64,
+    pub files: Vec<FileView<'a>>
+}
+
+#[derive(Clone, Debug)]
+pub struct FileView<'a> {
+    pub name: Cow<'a, str>,
+    pub content: Cow<'a, [u8]>,
+    raw_name: &'a [u8]
+}
+
+/// A cursor that tags every failure with the field being read and where it started.
+pub(crate) struct Reader<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) position: usize,
+    pub(crate) entry: Option<usize>
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn position(&self) -> u64 {
+        self.position as u64
+    }
+
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        self.bytes.get(self.position..).unwrap_or(&[])
+    }
+
+    pub(crate) fn error(&self, field: Field, offset: u64, reason: FofcError) -> FofcError {
+        FofcError::Parse { entry: self.entry, field, offset, reason: Box::new(reason) }
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self, field: Field) -> Result<[u8; N], FofcError> {
+        let offset = self.position();
+        let bytes: [u8; N] = self.remaining().get(..N)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| self.error(field, offset, FofcError::UnexpectedEof))?;
+
+        self.position += N;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self, field: Field) -> Result<u8, FofcError> {
+        self.array::<1>(field).map(|[b]| b)
+    }
+
+    pub(crate) fn u16(&mut self, field: Field) -> Result<u16, FofcError> {
+        self.array(field).map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self, field: Field) -> Result<u64, FofcError> {
+        self.array(field).map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn until_0x00(&mut self, field: Field) -> Result<&'a [u8], FofcError> {
+        let offset = self.position();
+        let rest = self.remaining();
+        let length = rest.iter().position(|&b| b == 0x00)
+            .ok_or_else(|| self.error(field, offset, FofcError::UnexpectedEof))?;
+
+        self.position += length + 1;
+        Ok(&rest[..length])
+    }
+
+    pub(crate) fn slice(&mut self, field: Field, length: u64) -> Result<&'a [u8], FofcError> {
+        let offset = self.position();
+        let rest = self.remaining();
+        let length = usize::try_from(length).ok()
+            .filter(|&length| length <= rest.len())
+            .ok_or_else(|| self.error(field, offset, FofcError::UnexpectedEof))?;
+
+        self.position += length;
+        Ok(&rest[..length])
+    }
+}
+
+pub(crate) fn decode_name<'a>(raw: &'a [u8], options: &ParseOptions) -> Result<Cow<'a, str>, FofcError> {
+    if options.mode == ParseMode::Strict && raw.is_empty() {
+        return Err(FofcError::EmptyName);
+    }
+
+    match options.name_decoding() {
+        NameDecoding::Lossy => Ok(String::from_utf8_lossy(raw)),
+        NameDecoding::Strict => core::str::from_utf8(raw).map(Cow::Borrowed).map_err(|_| FofcError::NameEncoding)
+    }
+}
+
+pub(crate) fn check_limit(limit: Limit, value: u64, max: u64) -> Result<(), FofcError> {
+    if value > max {
+        return Err(FofcError::LimitExceeded { limit, value, max });
+    }
+    Ok(())
+}
+
+/// Something `parse_recovering` skipped over instead of failing.
+#[derive(Debug)]
+pub struct ParseWarning {
+    /// Position of the entry in the input, or `None` if the problem isn't tied to one.
+    pub entry: Option<usize>,
+    pub field: Field,
+    /// Offset of the field in the input.
+    pub offset: u64,
+    pub reason: FofcError
+}
+
+impl ParseWarning {
+    pub(crate) fn new(error: FofcError) -> ParseWarning {
+        match error {
+            FofcError::Parse { entry, field, offset, reason } => ParseWarning { entry, field, offset, reason: *reason },
+            // the parser always attaches a location
+            reason => ParseWarning { entry: None, field: Field::Magic, offset: 0, reason }
+        }
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(entry) = self.entry {
+            write!(f, "entry {entry}: ")?;
+        }
+        write!(f, "{} at offset {:#x}: {}", self.field, self.offset, self.reason)
+    }
+}
+
+enum EntryError {
+    /// The entry was read past but can't be used; later entries are still readable.
+    Skip(FofcError),
+    /// The input ended or was malformed in a way that loses track of entry boundaries.
+    Stop(FofcError)
+}
+
+fn read_entry<'a>(reader: &mut Reader<'a>, options: &ParseOptions, total_size: &mut u64) -> Result<FileView<'a>, EntryError> {
+    let name_offset = reader.position();
+    let raw_name = reader.until_0x00(Field::FileName).map_err(EntryError::Stop)?;
+    let length_offset = reader.position();
+    let length = reader.u64(Field::FileLength).map_err(EntryError::Stop)?;
+
+    // check before anything is sized from `length`, so a corrupt length fails clearly
+    let remaining = reader.remaining().len() as u64;
+    if length > remaining {
+        let e = FofcError::TruncatedEntry { declared: length, remaining };
+        return Err(EntryError::Stop(reader.error(Field::FileLength, length_offset, e)));
+    }
+    let content = reader.slice(Field::FileContent, length).map_err(EntryError::Stop)?;
+
+    let name = check_limit(Limit::NameLength, raw_name.len() as u64, options.max_name_length as u64)
+        .and_then(|_| decode_name(raw_name, options))
+        .map_err(|e| EntryError::Skip(reader.error(Field::FileName, name_offset, e)))?;
+    check_limit(Limit::FileSize, length, options.max_file_size)
+        .and_then(|_| check_limit(Limit::TotalSize, total_size.saturating_add(length), options.max_total_size))
+        .map_err(|e| EntryError::Skip(reader.error(Field::FileLength, length_offset, e)))?;
+    *total_size += length;
+
+    Ok(FileView {
+        name,
+        content: Cow::Borrowed(content),
+        raw_name
+    })
+}
+
+impl<'a> ContainerView<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<ContainerView<'a>, Box<dyn Error>> {
+        ContainerView::parse_with_options(bytes, &ParseOptions::default())
+    }
+
+    pub fn parse_with_options(bytes: &'a [u8], options: &ParseOptions) -> Result<ContainerView<'a>, Box<dyn Error>> {
+        Ok(ContainerView::parse_inner(bytes, options, None)?)
+    }
+
+    /// Parses input from an untrusted source. See `HardenedOptions`.
+    #[cfg(feature = "hardened")]
+    pub fn parse_untrusted(bytes: &'a [u8], options: &HardenedOptions) -> Result<ContainerView<'a>, Box<dyn Error>> {
+        ContainerView::parse_with_options(bytes, options.as_parse_options())
+    }
+
+    /// Like `parse_with_options`, but entries that can't be decoded are skipped and reported
+    /// instead of failing the whole parse. Only a broken header is still an error.
+    pub fn parse_recovering(bytes: &'a [u8], options: &ParseOptions) -> Result<(ContainerView<'a>, Vec<ParseWarning>), Box<dyn Error>> {
+        let mut warnings = Vec::new();
+        let view = ContainerView::parse_inner(bytes, options, Some(&mut warnings))?;
+        Ok((view, warnings))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(len = bytes.len())))]
+    pub(crate) fn parse_inner(bytes: &'a [u8], options: &ParseOptions, mut warnings: Option<&mut Vec<ParseWarning>>) -> Result<ContainerView<'a>, FofcError> {
+        let mut reader = Reader { bytes, position: 0, entry: None };
+
+        let magic = reader.u8(Field::Magic)?;
+        if magic != MAGIC_NUMBER {
+            return Err(reader.error(Field::Magic, 0, FofcError::BadMagic { found: magic }));
+        }
+
+        let comment = String::from_utf8_lossy(reader.until_0x00(Field::Comment)?);
+        let x = reader.u64(Field::Timestamp)?;
+        let y = x.wrapping_add(Y_DIFFERENCE);
+        let z = x.wrapping_add(Z_DIFFERENCE);
+        let count_offset = reader.position();
+        let mut file_count = reader.u16(Field::FileCount)? as usize;
+        if let Err(e) = check_limit(Limit::FileCount, file_count as u64, options.max_file_count as u64) {
+            let e = reader.error(Field::FileCount, count_offset, e);
+            match warnings.as_deref_mut() {
+                Some(warnings) => {
+                    warnings.push(ParseWarning::new(e));
+                    file_count = options.max_file_count;
+                }
+                None => return Err(e)
+            }
+        }
+
+        let mut files: Vec<FileView<'a>> = Vec::new();
+        let mut positions: Map<Cow<'a, str>, usize> = Map::new();
+        let mut total_size: u64 = 0;
+        let mut stopped = false;
+
+        for entry in 0..file_count {
+            options.check_cancelled()?;
+            reader.entry = Some(entry);
+            let offset = reader.position();
+            let result = read_entry(&mut reader, options, &mut total_size);
+            if let Ok(file) = &result {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(entry, name = %file.name, length = file.content.len(), "read entry");
+                options.report_progress(&Progress {
+                    entry,
+                    entry_count: file_count,
+                    entry_name: &file.name,
+                    bytes_processed: reader.position(),
+                    total_bytes: bytes.len() as u64
+                });
+            }
+
+            let (e, stop) = match result {
+                Ok(file) if options.duplicate_policy == DuplicatePolicy::AllowDuplicates => {
+                    files.push(file);
+                    continue;
+                }
+                Ok(file) => match positions.get(&file.name) {
+                    None => {
+                        positions.insert(file.name.clone(), files.len());
+                        files.push(file);
+                        continue;
+                    }
+                    Some(&i) if options.duplicate_policy == DuplicatePolicy::ReplaceExisting => {
+                        files[i] = file;
+                        continue;
+                    }
+                    Some(_) => {
+                        let name = file.name.into_owned();
+                        (reader.error(Field::FileName, offset, FofcError::DuplicateName { name }), false)
+                    }
+                },
+                Err(EntryError::Skip(e)) => (e, false),
+                Err(EntryError::Stop(e)) => (e, true)
+            };
+
+            match warnings.as_deref_mut() {
+                Some(warnings) => warnings.push(ParseWarning::new(e)),
+                None => return Err(e)
+            }
+            if stop {
+                stopped = true;
+                break;
+            }
+        }
+        reader.entry = None;
+
+        let trailing = reader.remaining().len() as u64;
+        if options.mode == ParseMode::Strict && trailing > 0 && !stopped {
+            let e = reader.error(Field::TrailingData, reader.position(), FofcError::TrailingData { length: trailing });
+            match warnings {
+                Some(warnings) => warnings.push(ParseWarning::new(e)),
+                None => return Err(e)
+            }
+        }
+
+        Ok(ContainerView {comment, x, y, z, files})
+    }
+
+    pub fn get_file(&self, name: &str) -> Option<&FileView<'a>> {
+        self.files.iter().find(|f| f.name == name)
+    }
+
+    /// Copies everything out of the input buffer into an owned `Container`.
+    pub fn into_owned(self) -> Container {
+        let files: Vec<File> = self.files.into_iter().map(FileView::into_owned).collect();
+        Container::from_parts(self.comment.into_owned(), self.x, files)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut bytes: Vec<u8> = Vec::new();
+        encode_header(&mut bytes, &self.comment, self.x, check_header(&self.comment, self.files.len())?);
+
+        for f in self.files.iter() {
+            encode_entry(&mut bytes, &f.name, &f.content);
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl<'a> FileView<'a> {
+    /// The name exactly as it was stored, before any UTF-8 decoding.
+    pub fn name_bytes(&self) -> &'a [u8] {
+        self.raw_name
+    }
+
+    pub fn into_owned(self) -> File {
+        File::new(self.name.into_owned(), self.content.into_owned())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_borrows_input() {
+        let mut container = Container::new("View").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01, 0x02]));
+        container.add_file(File::new("b.bin", vec![0x03]));
+        let bytes = container.to_bytes().unwrap();
+
+        let mut view = ContainerView::parse(&bytes).unwrap();
+        let file = view.get_file("a.bin").unwrap();
+        assert!(matches!(file.content, Cow::Borrowed(_)));
+        assert_eq!(file.content.as_ref(), &[0x01, 0x02]);
+
+        // keep only one entry and write it back out
+        view.files.retain(|f| f.name == "b.bin");
+        let trimmed = Container::from_bytes(&view.to_bytes().unwrap()).unwrap();
+        assert_eq!(trimmed.files.len(), 1);
+        assert_eq!(trimmed.get_file("b.bin".to_string()).unwrap().content, vec![0x03]);
+    }
+
+    #[test]
+    fn limits_are_enforced() {
+        let mut container = Container::new("Limits").unwrap();
+        container.add_file(File::new("a.bin", vec![0; 16]));
+        container.add_file(File::new("b.bin", vec![0; 16]));
+        let bytes = container.to_bytes().unwrap();
+
+        let options = ParseOptions { max_file_size: 8, ..ParseOptions::default() };
+        let err = ContainerView::parse_with_options(&bytes, &options).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FofcError>().map(FofcError::kind),
+            Some(FofcError::LimitExceeded { limit: Limit::FileSize, value: 16, max: 8 })
+        ));
+
+        let options = ParseOptions { max_total_size: 24, ..ParseOptions::default() };
+        let err = ContainerView::parse_with_options(&bytes, &options).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FofcError>().map(FofcError::kind),
+            Some(FofcError::LimitExceeded { limit: Limit::TotalSize, .. })
+        ));
+
+        let options = ParseOptions { max_file_count: 1, ..ParseOptions::default() };
+        assert!(Container::from_bytes_with_options(&bytes, &options).is_err());
+    }
+
+    #[test]
+    fn name_length_is_limited() {
+        let mut container = Container::new("Limits").unwrap();
+        container.add_file(File::new("a-rather-long-name.bin", vec![]));
+        let bytes = container.to_bytes().unwrap();
+
+        let options = ParseOptions { max_name_length: 8, ..ParseOptions::default() };
+        let err = ContainerView::parse_with_options(&bytes, &options).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FofcError>().map(FofcError::kind),
+            Some(FofcError::LimitExceeded { limit: Limit::NameLength, .. })
+        ));
+    }
+
+    #[cfg(feature = "hardened")]
+    #[test]
+    fn hardened_options_only_tighten() {
+        let mut container = Container::new("Hardened").unwrap();
+        container.add_file(File::new("a.bin", vec![0; 16]));
+        let bytes = container.to_bytes().unwrap();
+        assert!(ContainerView::parse_untrusted(&bytes, &HardenedOptions::default()).is_ok());
+
+        let options = HardenedOptions::default().max_file_size(u64::MAX);
+        assert_eq!(options.as_parse_options().max_file_size, 64 << 20);
+        assert!(ContainerView::parse_untrusted(&bytes, &options.max_file_size(8)).is_err());
+    }
+
+    #[test]
+    fn strict_mode_rejects_malformed_input() {
+        let mut container = Container::new("Strict").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01]));
+        let mut bytes = container.to_bytes().unwrap();
+        assert!(ContainerView::parse_with_options(&bytes, &ParseOptions::strict()).is_ok());
+
+        bytes.push(0xFF);
+        assert!(ContainerView::parse(&bytes).is_ok());
+        let err = ContainerView::parse_with_options(&bytes, &ParseOptions::strict()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<FofcError>().map(FofcError::kind), Some(FofcError::TrailingData { length: 1 })));
+
+        let mut container = Container::new("Strict").unwrap();
+        container.add_file(File::new("", vec![0x01]));
+        let bytes = container.to_bytes().unwrap();
+        let err = ContainerView::parse_with_options(&bytes, &ParseOptions::strict()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<FofcError>().map(FofcError::kind), Some(FofcError::EmptyName)));
+
+        // swap the name's first byte for one that isn't valid UTF-8
+        let mut container = Container::new("Strict").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01]));
+        let mut bytes = container.to_bytes().unwrap();
+        let name_at = 1 + "Strict".len() + 1 + 8 + 2;
+        bytes[name_at] = 0xC3;
+        assert_eq!(ContainerView::parse(&bytes).unwrap().files[0].name, "\u{FFFD}.bin");
+        let err = ContainerView::parse_with_options(&bytes, &ParseOptions::strict()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<FofcError>().map(FofcError::kind), Some(FofcError::NameEncoding)));
+    }
+
+    #[test]
+    fn name_decoding_can_be_chosen_per_parse() {
+        let mut container = Container::new("").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01]));
+        let mut bytes = container.to_bytes().unwrap();
+        bytes[1 + 1 + 8 + 2] = 0xC3;
+
+        let options = ParseOptions { name_decoding: Some(NameDecoding::Strict), ..ParseOptions::default() };
+        let err = ContainerView::parse_with_options(&bytes, &options).unwrap_err();
+        assert!(matches!(err.downcast_ref::<FofcError>().map(FofcError::kind), Some(FofcError::NameEncoding)));
+
+        let options = ParseOptions { name_decoding: Some(NameDecoding::Lossy), ..ParseOptions::strict() };
+        let view = ContainerView::parse_with_options(&bytes, &options).unwrap();
+        assert_eq!(view.files[0].name, "\u{FFFD}.bin");
+        assert_eq!(view.files[0].name_bytes(), b"\xC3.bin");
+    }
+
+    #[test]
+    fn recovering_parse_skips_bad_entries() {
+        let mut container = Container::new("Recover").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01]));
+        container.add_file(File::new("", vec![0x02]));
+        container.add_file(File::new("c.bin", vec![0x03, 0x04]));
+        let mut bytes = container.to_bytes().unwrap();
+        bytes.pop(); // the last entry is now one byte short
+
+        assert!(ContainerView::parse_with_options(&bytes, &ParseOptions::strict()).is_err());
+        let (view, warnings) = ContainerView::parse_recovering(&bytes, &ParseOptions::strict()).unwrap();
+        assert_eq!(view.files.len(), 1);
+        assert_eq!(view.files[0].name, "a.bin");
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].entry, Some(1));
+        assert!(matches!(warnings[0].reason, FofcError::EmptyName));
+        assert_eq!(warnings[1].entry, Some(2));
+        assert_eq!(warnings[1].field, Field::FileLength);
+        assert!(matches!(warnings[1].reason, FofcError::TruncatedEntry { declared: 2, remaining: 1 }));
+    }
+
+    #[test]
+    fn duplicate_policy_applies_while_parsing() {
+        let mut container = Container::new("Duplicates").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01]));
+        container.add_file(File::new("a.bin", vec![0x02]));
+        let bytes = container.to_bytes().unwrap();
+        assert_eq!(ContainerView::parse(&bytes).unwrap().files.len(), 2);
+
+        let options = ParseOptions { duplicate_policy: DuplicatePolicy::ReplaceExisting, ..ParseOptions::default() };
+        let view = ContainerView::parse_with_options(&bytes, &options).unwrap();
+        assert_eq!(view.files.len(), 1);
+        assert_eq!(view.files[0].content.as_ref(), &[0x02]);
+
+        let options = ParseOptions { duplicate_policy: DuplicatePolicy::Reject, ..ParseOptions::default() };
+        let err = ContainerView::parse_with_options(&bytes, &options).unwrap_err();
+        assert!(matches!(err.downcast_ref::<FofcError>().map(FofcError::kind), Some(FofcError::DuplicateName { .. })));
+    }
+
+    #[test]
+    fn cancelled_parse_stops() {
+        use crate::CancellationToken;
+
+        let mut container = Container::new("Cancel").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01]));
+        let bytes = container.to_bytes().unwrap();
+
+        let token = CancellationToken::new();
+        let options = ParseOptions { cancel: Some(token.clone()), ..ParseOptions::default() };
+        assert!(ContainerView::parse_with_options(&bytes, &options).is_ok());
+
+        token.cancel();
+        let err = ContainerView::parse_recovering(&bytes, &options).unwrap_err();
+        assert!(matches!(err.downcast_ref::<FofcError>(), Some(FofcError::Cancelled)));
+    }
+
+    #[test]
+    fn progress_is_reported_per_entry() {
+        use std::sync::{Arc, Mutex};
+        use crate::ProgressHook;
+
+        let mut container = Container::new("Progress").unwrap();
+        container.add_file(File::new("a.bin", vec![0x01]));
+        container.add_file(File::new("b.bin", vec![0x02, 0x03]));
+        let bytes = container.to_bytes().unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let options = ParseOptions {
+            on_progress: Some(ProgressHook::new(move |p| {
+                log.lock().unwrap().push((p.entry, p.entry_name.to_string(), p.bytes_processed, p.total_bytes))
+            })),
+            ..ParseOptions::default()
+        };
+        ContainerView::parse_with_options(&bytes, &options).unwrap();
+
+        let total = bytes.len() as u64;
+        assert_eq!(*seen.lock().unwrap(), vec![(0, "a.bin".to_string(), total - 16, total), (1, "b.bin".to_string(), total, total)]);
+    }
+
+    #[test]
+    fn errors_carry_location() {
+        let mut container = Container::new("").unwrap();
+        container.add_file(File::new("a", vec![0x01]));
+        container.add_file(File::new("b", vec![0x02, 0x03]));
+        let mut bytes = container.to_bytes().unwrap();
+        bytes.truncate(bytes.len() - 2);
+
+        let err = ContainerView::parse(&bytes).unwrap_err();
+        match err.downcast_ref::<FofcError>() {
+            Some(FofcError::Parse { entry, field, offset, reason }) => {
+                assert_eq!(*entry, Some(1));
+                assert_eq!(*field, Field::FileLength);
+                // magic, comment terminator, x, count, then "a\0" + length + content, "b\0"
+                assert_eq!(*offset, 1 + 1 + 8 + 2 + 2 + 8 + 1 + 2);
+                assert!(matches!(**reason, FofcError::TruncatedEntry { declared: 2, remaining: 0 }));
+            }
+            other => panic!("unexpected error {other:?}")
+        }
+        assert!(err.to_string().starts_with("entry 1: file length field at offset 0x19"));
+
+        let err = ContainerView::parse(&[0x00]).unwrap_err();
+        assert!(matches!(err.downcast_ref::<FofcError>().map(FofcError::kind), Some(FofcError::BadMagic { found: 0x00 })));
+    }
+}
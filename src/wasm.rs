@@ -0,0 +1,50 @@
+//! JavaScript bindings built with wasm-bindgen.
+
+use wasm_bindgen::prelude::*;
+use crate::{Container, File};
+
+/// A container exposed to JavaScript as `FofcContainer`.
+#[wasm_bindgen(js_name = FofcContainer)]
+pub struct WasmContainer {
+    container: Container
+}
+
+#[wasm_bindgen(js_class = FofcContainer)]
+impl WasmContainer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(comment: &str) -> Result<WasmContainer, JsError> {
+        let container = Container::new(comment).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(WasmContainer { container })
+    }
+
+    /// Parses a container from a `Uint8Array`.
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<WasmContainer, JsError> {
+        let container = Container::from_bytes(bytes).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(WasmContainer { container })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn comment(&self) -> String {
+        self.container.comment.clone()
+    }
+
+    /// Entry names, in order.
+    pub fn names(&self) -> Vec<String> {
+        self.container.files.iter().map(|f| f.name.clone()).collect()
+    }
+
+    /// An entry's content, or `undefined` if there's no entry with that name.
+    pub fn get(&self, name: &str) -> Option<Vec<u8>> {
+        self.container.get_file(name.to_string()).map(|f| f.content.to_vec())
+    }
+
+    pub fn add(&mut self, name: &str, content: &[u8]) -> Result<(), JsError> {
+        self.container.try_add_file(File::new(name, content.to_vec())).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.container.to_bytes().map_err(|e| JsError::new(&e.to_string()))
+    }
+}